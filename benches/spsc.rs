@@ -0,0 +1,86 @@
+//! Throughput of SPSC channels, generic `Owned` sequences against the specialized one.
+//!
+//! Run with `cargo bench --bench spsc`. `burst` fills and drains the buffer
+//! in a single thread to measure the cost of each operation alone,
+//! and `threaded` sends messages across threads.
+
+extern crate ringbuf;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ringbuf::spsc;
+use ringbuf::queue::SendError;
+
+const COUNT: usize = 1_000_000;
+const CAPACITY: usize = 1024;
+const ROUNDS: usize = 5;
+
+/// Generates the benchmarks for given channel constructor, as halves don't share a trait.
+macro_rules! bench {
+    ($name:ident, $channel:path) => {
+        mod $name {
+            use super::*;
+
+            pub fn burst() -> Duration {
+                let (mut tx, mut rx) = $channel(CAPACITY);
+                let start = Instant::now();
+
+                for _ in 0..COUNT / CAPACITY {
+                    for i in 0..CAPACITY {
+                        tx.try_send(i).unwrap();
+                    }
+                    for _ in 0..CAPACITY {
+                        rx.try_recv().unwrap();
+                    }
+                }
+
+                start.elapsed()
+            }
+
+            pub fn threaded() -> Duration {
+                let (mut tx, mut rx) = $channel(CAPACITY);
+                let start = Instant::now();
+
+                let handle = thread::spawn(move|| {
+                    for i in 0..COUNT {
+                        let mut msg = i;
+                        while let Err(SendError::BufferFull(v)) = tx.try_send(msg) {
+                            msg = v;
+                            thread::yield_now();
+                        }
+                    }
+                });
+
+                let mut received = 0;
+                while received < COUNT {
+                    match rx.try_recv() {
+                        Ok(Some(_)) => received += 1,
+                        _ => thread::yield_now(),
+                    }
+                }
+
+                handle.join().unwrap();
+                start.elapsed()
+            }
+        }
+    };
+}
+
+bench!(generic, spsc::channel::<usize>);
+bench!(fast, spsc::fast::channel::<usize>);
+
+fn report(name: &str, count: usize, f: fn() -> Duration) {
+    let best = (0..ROUNDS).map(|_| f()).min().unwrap();
+    let nanos = best.as_secs() * 1_000_000_000 + u64::from(best.subsec_nanos());
+    println!("{:16} {:>8.2} ns/msg", name, nanos as f64 / count as f64);
+}
+
+fn main() {
+    let burst = COUNT / CAPACITY * CAPACITY;
+
+    report("generic burst", burst, generic::burst);
+    report("fast burst", burst, fast::burst);
+    report("generic threaded", COUNT, generic::threaded);
+    report("fast threaded", COUNT, fast::threaded);
+}
@@ -0,0 +1,83 @@
+//! Cost of notifying waiters on every commit of bounded channels.
+//!
+//! Run with `cargo bench --bench waiters`. `burst` fills and drains the buffer
+//! in a single thread, so every notification finds no waiter and takes the fast path.
+//! `blocking` sends messages across threads with blocking calls, which park on the waiter list.
+//! `spsc::fast` channel never notifies, so it's the baseline of the fast path.
+
+extern crate ringbuf;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ringbuf::queue::bounded;
+use ringbuf::sequence::owned::Owned;
+use ringbuf::sequence::shared::Shared;
+use ringbuf::spsc;
+
+const COUNT: usize = 1_000_000;
+const CAPACITY: usize = 1024;
+const ROUNDS: usize = 5;
+
+/// Generates the benchmarks for given channel constructor, as halves don't share a trait.
+macro_rules! bench {
+    ($name:ident, $channel:expr) => {
+        mod $name {
+            use super::*;
+
+            pub fn burst() -> Duration {
+                let (mut tx, mut rx) = $channel(CAPACITY);
+                let start = Instant::now();
+
+                for _ in 0..COUNT / CAPACITY {
+                    for i in 0..CAPACITY {
+                        tx.try_send(i).unwrap();
+                    }
+                    for _ in 0..CAPACITY {
+                        rx.try_recv().unwrap();
+                    }
+                }
+
+                start.elapsed()
+            }
+        }
+    };
+}
+
+bench!(owned, bounded::queue::<Owned, Owned, usize>);
+bench!(shared, bounded::queue::<Shared, Shared, usize>);
+bench!(fast, spsc::fast::channel::<usize>);
+
+fn blocking() -> Duration {
+    // Small buffer so both halves park often
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(16);
+    let start = Instant::now();
+
+    let handle = thread::spawn(move|| {
+        for i in 0..COUNT {
+            tx.send_blocking(i).unwrap();
+        }
+    });
+
+    for _ in 0..COUNT {
+        rx.recv_blocking().unwrap();
+    }
+
+    handle.join().unwrap();
+    start.elapsed()
+}
+
+fn report(name: &str, count: usize, f: fn() -> Duration) {
+    let best = (0..ROUNDS).map(|_| f()).min().unwrap();
+    let nanos = best.as_secs() * 1_000_000_000 + u64::from(best.subsec_nanos());
+    println!("{:16} {:>8.2} ns/msg", name, nanos as f64 / count as f64);
+}
+
+fn main() {
+    let burst = COUNT / CAPACITY * CAPACITY;
+
+    report("owned burst", burst, owned::burst);
+    report("shared burst", burst, shared::burst);
+    report("fast burst", burst, fast::burst);
+    report("blocking", COUNT, blocking);
+}
@@ -1,33 +1,129 @@
 
-use std::sync::Arc;
-use std::ops::Drop;
-use std::ptr;
-use std::cmp::PartialEq;
-use std::fmt;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use core::ops::Drop;
+use core::ptr;
+use core::cmp::PartialEq;
+use core::fmt;
 
 use counter::{Counter, CounterRange, COUNTER_VALID_RANGE};
 
 pub trait BufRange {
     fn range(&self) -> CounterRange;
+
+    /// Whether given slot in the range holds no message, as its sender abandoned it.
+    fn is_skipped(&self, _count: Counter) -> bool {
+        false
+    }
 }
 
 pub struct Buffer<H: BufRange, T> {
     inner: Arc<Inner<H, T>>,
-    ptr: *mut T,
-    mask: usize,
 }
 
-#[derive(Debug)]
 struct Inner<H: BufRange, T> {
     head: H,
-    storage: Vec<T>,
+    /// Most recent storage, which links to older ones still in use.
+    storage: AtomicPtr<Storage<T>>,
+}
+
+/// Slots of the buffer, which holds messages from `start`.
+///
+/// Messages before it are held by the `prev` storage, which was replaced by this one.
+struct Storage<T> {
+    /// Owns the memory `ptr` points to.
+    _slots: Vec<T>,
+    ptr: *mut T,
+    index: Index,
+    start: Counter,
+    prev: AtomicPtr<Storage<T>>,
+}
+
+/// How counters are mapped to slots of the storage.
+#[derive(Debug, Clone, Copy)]
+enum Index {
+    /// Fast path for power of 2 capacity.
+    Mask(usize),
+    /// Storage of arbitrary capacity.
+    ///
+    /// Counters are mapped consistently only until they wrap around,
+    /// so it's only allowed for 64bit targets where it takes `2^63` messages.
+    Modulo(usize),
 }
 
 unsafe impl<H: BufRange, T: Send> Send for Buffer<H, T> {}
 unsafe impl<H: BufRange, T: Send> Sync for Buffer<H, T> {}
 
-fn index(count: Counter, mask: usize) -> isize {
-    (count & mask) as isize
+impl Index {
+    fn new(capacity: usize) -> Self {
+        if capacity.is_power_of_two() {
+            Index::Mask(capacity - 1)
+        } else if cfg!(target_pointer_width = "64") {
+            Index::Modulo(capacity)
+        } else {
+            panic!("Capacity should be power of 2 on non-64bit targets")
+        }
+    }
+
+    fn capacity(self) -> usize {
+        match self {
+            Index::Mask(mask) => mask + 1,
+            Index::Modulo(capacity) => capacity,
+        }
+    }
+
+    fn of(self, count: Counter) -> usize {
+        match self {
+            Index::Mask(mask) => count & mask,
+            Index::Modulo(capacity) => count % capacity,
+        }
+    }
+}
+
+impl<T> Storage<T> {
+    fn alloc(capacity: usize, start: Counter, prev: *mut Storage<T>) -> *mut Storage<T> {
+        let mut slots = Vec::with_capacity(capacity);
+        let ptr = slots.as_mut_ptr();
+
+        Box::into_raw(Box::new(Storage {
+            _slots: slots,
+            ptr,
+            index: Index::new(capacity),
+            start,
+            prev: AtomicPtr::new(prev),
+        }))
+    }
+
+    /// Find the storage which holds given counter, from this one and older ones.
+    fn locate(&self, count: Counter) -> &Storage<T> {
+        let mut storage = self;
+
+        // Older messages may remain in previous storages after the buffer grows
+        while count < storage.start {
+            match unsafe { storage.prev.load(Ordering::Acquire).as_ref() } {
+                Some(prev) => storage = prev,
+                None => break,
+            }
+        }
+
+        storage
+    }
+
+    fn get(&self, count: Counter) -> *mut T {
+        unsafe {
+            self.ptr.add(self.index.of(count))
+        }
+    }
+
+    /// Free given storage and every older ones. Messages in them are not dropped.
+    unsafe fn free(mut storage: *mut Storage<T>) {
+        while !storage.is_null() {
+            let boxed = Box::from_raw(storage);
+            storage = boxed.prev.load(Ordering::Relaxed);
+        }
+    }
 }
 
 /// Buffer capacity is limited to half of valid counter range.
@@ -36,30 +132,33 @@ fn index(count: Counter, mask: usize) -> isize {
 /// But well, even on 32bit OS `Buffer<_, usize>` can takes 1 GiB of memory. Isn't it enough?
 const MAX_BUF_CAPACITY: usize = COUNTER_VALID_RANGE / 2;
 
+/// Whether given capacity can be used to create or grow a buffer.
+pub(crate) const fn is_valid_capacity(capacity: usize) -> bool {
+    capacity != 0 && capacity < COUNTER_VALID_RANGE &&
+        (capacity.is_power_of_two() || cfg!(target_pointer_width = "64"))
+}
+
 impl<H: BufRange, T> Buffer<H, T> {
+    /// Create a buffer with given capacity.
+    ///
+    /// Power of 2 capacity is recommended, as other ones need division to locate slots.
     pub fn new(head: H, capacity: usize) -> Self {
-        assert!(capacity.is_power_of_two(), "Capacity should be power of 2");
+        assert!(capacity != 0, "Capacity should not be zero");
         assert!(capacity < COUNTER_VALID_RANGE,
             "Capacity should be lower or equal than {:#X}", MAX_BUF_CAPACITY);
 
-        let mut storage = Vec::with_capacity(capacity);
-        let ptr = storage.as_mut_ptr();
-        let mask = capacity - 1;
-
-        let inner = Arc::new(Inner {
-            head,
-            storage,
-        });
+        let storage = Storage::alloc(capacity, Counter::default(), ptr::null_mut());
 
         Buffer {
-            inner,
-            ptr,
-            mask,
+            inner: Arc::new(Inner {
+                head,
+                storage: AtomicPtr::new(storage),
+            }),
         }
     }
 
     pub fn capacity(&self) -> usize {
-        self.mask + 1
+        self.storage().index.capacity()
     }
 
     pub fn head(&self) -> &H {
@@ -67,22 +166,72 @@ impl<H: BufRange, T> Buffer<H, T> {
     }
 
     pub fn get(&self, count: Counter) -> *mut T {
-        unsafe {
-            self.ptr.offset(index(count, self.mask))
+        self.storage().locate(count).get(count)
+    }
+
+    /// Replace the storage with larger one, which holds messages from `start`.
+    ///
+    /// Messages before `start` are not moved, so pointers to them are still valid.
+    ///
+    /// # Safety
+    ///
+    /// Slots from `start` should not be accessed before this call,
+    /// and accesses to them after this call should be synchronized with it,
+    /// like by acquiring the counter released after writing them.
+    /// Nobody else should grow or release this buffer concurrently.
+    #[cfg(feature = "std")]
+    pub(crate) unsafe fn grow(&self, capacity: usize, start: Counter) {
+        debug_assert!(is_valid_capacity(capacity) && capacity > self.capacity());
+
+        let prev = self.inner.storage.load(Ordering::Acquire);
+        let storage = Storage::alloc(capacity, start, prev);
+        self.inner.storage.store(storage, Ordering::Release);
+    }
+
+    /// Free previous storages which only hold messages before `until`.
+    ///
+    /// # Safety
+    ///
+    /// Slots before `until` should not be accessed anymore.
+    /// Nobody else should grow or release this buffer concurrently.
+    #[cfg(feature = "std")]
+    pub(crate) unsafe fn release(&self, until: Counter) {
+        let mut storage = self.storage();
+
+        loop {
+            let prev = storage.prev.load(Ordering::Acquire);
+
+            match prev.as_ref() {
+                None => return,
+                Some(_) if storage.start <= until => {
+                    storage.prev.store(ptr::null_mut(), Ordering::Release);
+                    Storage::free(prev);
+                    return;
+                }
+                Some(prev) => storage = prev,
+            }
         }
     }
+
+    fn storage(&self) -> &Storage<T> {
+        // Current storage is freed only with the buffer itself
+        unsafe { &*self.inner.storage.load(Ordering::Acquire) }
+    }
 }
 
 impl<H: BufRange, T> Drop for Inner<H, T> {
     fn drop(&mut self) {
-        let elems = self.storage.as_mut_ptr();
-        let mask = self.storage.capacity() - 1;
+        let storage = *self.storage.get_mut();
 
-        for count in self.head.range() {
+        for count in self.head.range().filter(|&count| !self.head.is_skipped(count)) {
             unsafe {
-                ptr::drop_in_place(elems.offset(index(count, mask)));
+                ptr::drop_in_place((*storage).locate(count).get(count));
             }
         }
+
+        unsafe {
+            Storage::free(storage);
+        }
     }
 }
 
@@ -90,8 +239,6 @@ impl<H: BufRange, T> Clone for Buffer<H, T> {
     fn clone(&self) -> Self {
         Buffer {
             inner: self.inner.clone(),
-            ptr: self.ptr,
-            mask: self.mask,
         }
     }
 }
@@ -117,9 +264,11 @@ struct PrintContents<'a, H: BufRange + fmt::Debug + 'a, T: fmt::Debug + 'a>(&'a
 
 impl<'a, H: BufRange + fmt::Debug, T: fmt::Debug> fmt::Debug for PrintContents<'a, H, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let head = self.0.head();
+
         f.debug_list()
-            .entries(self.0.head().range().map(|count| {
-                unsafe { &*self.0.ptr.offset(index(count, self.0.mask)) }
+            .entries(head.range().filter(|&count| !head.is_skipped(count)).map(|count| {
+                unsafe { &*self.0.get(count) }
             }))
             .finish()
     }
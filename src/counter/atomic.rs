@@ -1,12 +1,14 @@
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::mem::size_of;
-use std::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::mem::size_of;
+use core::fmt;
 
 use super::Counter;
 
 const PADDING_LEN: usize = 64 - 2 * size_of::<AtomicUsize>();
 
+/// Its layout is fixed so it can be shared by different processes.
+#[repr(C)]
 pub struct AtomicCounter {
     counter: AtomicUsize,
     last: AtomicUsize,
@@ -26,11 +28,11 @@ fn make(value: usize) -> Option<Counter> {
 
 impl AtomicCounter {
     /// Create new `AtomicCounter` from given counter.
-    pub fn new(value: Counter) -> Self {
+    pub const fn new(value: Counter) -> Self {
         AtomicCounter {
-            counter: value.0.into(),
+            counter: AtomicUsize::new(value.0),
             // Initially invalid counter
-            last: 1.into(),
+            last: AtomicUsize::new(1),
             _padding: [0; PADDING_LEN],
         }
     }
@@ -45,9 +47,27 @@ impl AtomicCounter {
     }
 
     /// Increase internal counter by 1. Returns previous counter or `None` if closed.
+    ///
+    /// It's sequentially consistent, so waiters can be notified after it without a fence.
     pub fn incr(&self) -> Option<Counter> {
         // It actually increase `0b10` as its LSB is reserved for close detection.
-        make(self.counter.fetch_add(0b10, Ordering::Release))
+        make(self.counter.fetch_add(0b10, Ordering::SeqCst))
+    }
+
+    /// Increase internal counter by `n`. Returns previous counter or `None` if closed.
+    ///
+    /// It's sequentially consistent, so waiters can be notified after it without a fence.
+    pub fn add(&self, n: usize) -> Option<Counter> {
+        make(self.counter.fetch_add(n << 1, Ordering::SeqCst))
+    }
+
+    /// Overwrite internal counter with plain store.
+    ///
+    /// This is cheaper than `incr` or `add`, but only valid if the caller is the only one
+    /// who modifies this counter, as it never closes.
+    pub fn store(&self, value: Counter) {
+        debug_assert!(make(self.counter.load(Ordering::Relaxed)).is_some());
+        self.counter.store(value.0, Ordering::Release);
     }
 
     /// Conditionally change internal counter with given ordering.
@@ -57,12 +77,9 @@ impl AtomicCounter {
     pub fn comp_swap(
         &self, cond: Counter, value: Counter, ord: Ordering
     ) -> Result<(), Option<Counter>> {
-        let res = self.counter.compare_and_swap(cond.0, value.0, ord);
-
-        if res == cond.0 {
-            Ok(())
-        } else {
-            Err(make(res))
+        match self.counter.compare_exchange(cond.0, value.0, ord, Ordering::Acquire) {
+            Ok(_) => Ok(()),
+            Err(res) => Err(make(res)),
         }
     }
 
@@ -78,12 +95,9 @@ impl AtomicCounter {
                 return;
             }
 
-            let prev = self.counter.compare_and_swap(value, LSB, Ordering::Release);
-
-            if prev == value {
-                break;
-            } else {
-                value = prev;
+            match self.counter.compare_exchange(value, LSB, Ordering::Release, Ordering::Acquire) {
+                Ok(_) => break,
+                Err(prev) => value = prev,
             }
         }
 
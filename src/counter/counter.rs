@@ -1,7 +1,7 @@
 
-use std::cmp::{self, PartialOrd};
-use std::ops;
-use std::fmt;
+use core::cmp::{self, PartialOrd};
+use core::ops;
+use core::fmt;
 
 /// Overflow-safe ever-increasing pointer-sized counter.
 ///
@@ -39,7 +39,7 @@ pub struct CounterRange {
 pub const COUNTER_VALID_RANGE: usize = 1 << (WORD - 3);
 const COUNTER_FULL_RANGE: isize = 1 << (WORD - 1);
 
-const WORD: usize = ::std::mem::size_of::<usize>() * 8;
+const WORD: usize = ::core::mem::size_of::<usize>() * 8;
 const MSB: usize = 0b11 << (WORD - 2);
 
 fn msb_pp(value: Counter) -> bool {
@@ -64,7 +64,7 @@ fn test_counter_utils() {
 
 impl Counter {
     /// Create new counter initialized with given value.
-    pub fn new(init: usize) -> Self {
+    pub const fn new(init: usize) -> Self {
         Counter(init << 1)
     }
 
@@ -89,8 +89,15 @@ impl Iterator for CounterRange {
             Some(res)
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.end - self.start) as usize;
+        (len, Some(len))
+    }
 }
 
+impl ExactSizeIterator for CounterRange {}
+
 impl PartialOrd<Self> for Counter {
     fn partial_cmp(&self, rhs: &Self) -> Option<cmp::Ordering> {
         if msb_nn(*self) && msb_pp(*rhs) {
@@ -121,6 +128,7 @@ impl ops::Sub<Self> for Counter {
 impl ops::Add<usize> for Counter {
     type Output = Self;
 
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn add(self, rhs: usize) -> Self {
         Counter(self.0.wrapping_add(rhs << 1))
     }
@@ -135,6 +143,7 @@ impl ops::AddAssign<usize> for Counter {
 impl ops::Sub<usize> for Counter {
     type Output = Self;
 
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn sub(self, rhs: usize) -> Self {
         Counter(self.0.wrapping_sub(rhs << 1))
     }
@@ -154,6 +163,14 @@ impl ops::BitAnd<usize> for Counter {
     }
 }
 
+impl ops::Rem<usize> for Counter {
+    type Output = usize;
+
+    fn rem(self, rhs: usize) -> usize {
+        (self.0 >> 1) % rhs
+    }
+}
+
 impl ops::BitOr<usize> for Counter {
     type Output = usize;
 
@@ -1,4 +1,5 @@
 
+#[allow(clippy::module_inception)]
 mod counter;
 mod atomic;
 
@@ -6,4 +7,6 @@ pub use self::counter::{Counter, CounterRange, COUNTER_VALID_RANGE};
 pub use self::atomic::AtomicCounter;
 
 #[cfg(test)]
+// Lints of newer toolchains on the original tests
+#[allow(clippy::legacy_numeric_constants)]
 mod tests;
@@ -0,0 +1,12 @@
+//! Errors shared by channels, which don't depend on `std`.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendError<T> {
+    BufferFull(T),
+    Closed(T),
+    /// Every receivers are dropped or closed the channel.
+    Disconnected(T),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecvError;
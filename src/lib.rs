@@ -1,6 +1,19 @@
 
+//! Without default `std` feature, this crate only requires `alloc`.
+//! Blocking channels in `queue` module need `std`, but `counter`, `sequence`
+//! and `buffer` modules, and rings in `spsc` module remain available.
+
+// Tests always run with `std`
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(any(feature = "std", test))]
+extern crate core;
+extern crate alloc;
+
 #[cfg(test)]
 extern crate rand;
+#[cfg(feature = "futures")]
+extern crate futures;
 
 pub mod counter;
 
@@ -8,4 +21,17 @@ pub mod buffer;
 pub mod role;
 
 pub mod sequence;
+pub mod error;
+#[cfg(feature = "std")]
 pub mod queue;
+
+pub mod spsc;
+#[cfg(feature = "std")]
+pub mod mpsc;
+#[cfg(feature = "std")]
+pub mod spmc;
+#[cfg(feature = "std")]
+pub mod mpmc;
+
+#[cfg(test)]
+mod test_util;
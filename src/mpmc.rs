@@ -0,0 +1,23 @@
+//! Multi producer multi consumer channel.
+//!
+//! Both halves can be cloned and share `Shared` sequence of each side.
+//! Each message is received by only one of the receivers.
+//!
+//! ```
+//! let (tx, rx) = ringbuf::mpmc::channel::<u32>(4);
+//! let (mut tx2, mut rx2) = (tx.clone(), rx.clone());
+//! drop((tx, rx));
+//!
+//! tx2.try_send(1).unwrap();
+//! assert_eq!(rx2.try_recv(), Ok(Some(1)));
+//! ```
+
+use sequence::shared::Shared;
+use queue::bounded;
+
+pub type Sender<T> = bounded::Sender<Shared, Shared, T>;
+pub type Receiver<T> = bounded::Receiver<Shared, Shared, T>;
+
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    bounded::queue(capacity)
+}
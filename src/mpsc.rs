@@ -0,0 +1,26 @@
+//! Multi producer single consumer channel.
+//!
+//! Senders can be cloned and share `Shared` sequence, while the receiver
+//! uses `Owned` sequence which claims slots without atomic read-modify-write.
+//! Slots abandoned by senders, like by dropped `SendSlot`, are stepped over by the receiver.
+//!
+//! ```
+//! let (mut tx, mut rx) = ringbuf::mpsc::channel::<u32>(4);
+//! let mut tx2 = tx.clone();
+//!
+//! tx.try_send(1).unwrap();
+//! tx2.try_send(2).unwrap();
+//! assert_eq!(rx.try_recv(), Ok(Some(1)));
+//! assert_eq!(rx.try_recv(), Ok(Some(2)));
+//! ```
+
+use sequence::owned::Owned;
+use sequence::shared::Shared;
+use queue::bounded;
+
+pub type Sender<T> = bounded::Sender<Shared, Owned, T>;
+pub type Receiver<T> = bounded::Receiver<Shared, Owned, T>;
+
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    bounded::queue(capacity)
+}
@@ -0,0 +1,65 @@
+use std::iter::FusedIterator;
+use std::ops::Drop;
+use std::fmt;
+
+use sequence::Sequence;
+
+use super::head::ReceiverBatch;
+
+/// Iterator returned by `Receiver::drain`.
+pub struct Drain<'a, S: Sequence + 'a, R: Sequence + 'a, T: 'a> {
+    batch: Option<ReceiverBatch<'a, S, R, T>>,
+}
+
+impl<'a, S: Sequence, R: Sequence, T> Drain<'a, S, R, T> {
+    pub(super) fn new(batch: Option<ReceiverBatch<'a, S, R, T>>) -> Self {
+        Drain {
+            batch,
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> Iterator for Drain<'a, S, R, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match &mut self.batch {
+            Some(batch) if batch.remaining() != 0 => {
+                let msg = batch.advance(());
+                // Commit as it's taken, so leaking this iterator never exposes it again.
+                // Receiver sequence is never closed, so commit never fails
+                let _ = batch.commit();
+                Some(msg)
+            }
+            _ => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> ExactSizeIterator for Drain<'a, S, R, T> {
+    fn len(&self) -> usize {
+        self.batch.as_ref().map_or(0, ReceiverBatch::remaining)
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> FusedIterator for Drain<'a, S, R, T> {}
+
+impl<'a, S: Sequence, R: Sequence, T> Drop for Drain<'a, S, R, T> {
+    fn drop(&mut self) {
+        // Drop messages not taken yet, which commits their slots.
+        for _ in self {}
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> fmt::Debug for Drain<'a, S, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Drain")
+            .field("remaining", &self.len())
+            .finish()
+    }
+}
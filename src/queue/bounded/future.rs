@@ -0,0 +1,112 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::ops::Drop;
+
+use sequence::Sequence;
+
+use super::{Sender, Receiver, SendError};
+use super::half::AdvanceError;
+
+/// Future returned by `Sender::send`.
+///
+/// Dropping this future before completion deregisters its waker from the channel
+/// and drops the message.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct SendFuture<'a, S: Sequence + 'a, R: Sequence + 'a, T: 'a> {
+    sender: &'a mut Sender<S, R, T>,
+    msg: Option<T>,
+}
+
+/// Future returned by `Receiver::recv`.
+///
+/// Dropping this future before completion deregisters its waker from the channel.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct RecvFuture<'a, S: Sequence + 'a, R: Sequence + 'a, T: 'a> {
+    receiver: &'a mut Receiver<S, R, T>,
+}
+
+impl<'a, S: Sequence, R: Sequence, T> SendFuture<'a, S, R, T> {
+    pub(super) fn new(sender: &'a mut Sender<S, R, T>, msg: T) -> Self {
+        SendFuture {
+            sender,
+            msg: Some(msg),
+        }
+    }
+
+    /// Cancel sending and take back the message.
+    ///
+    /// Returns `None` if the message is already sent.
+    pub fn cancel(mut self) -> Option<T> {
+        self.msg.take()
+    }
+}
+
+// Sound for the same reason as `Sender`, the message is only moved by value.
+impl<'a, S: Sequence, R: Sequence, T> Unpin for SendFuture<'a, S, R, T> {}
+
+impl<'a, S: Sequence, R: Sequence, T> Future for SendFuture<'a, S, R, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let msg = this.msg.take().expect("SendFuture polled after completion");
+
+        let half = match &mut this.sender.half {
+            Some(half) => half,
+            None => return Poll::Ready(Err(SendError::Closed(msg))),
+        };
+
+        match half.poll_advance(msg, cx.waker()) {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(AdvanceError::BufferFull(msg)) => {
+                this.msg = Some(msg);
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(SendError::from(e))),
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> Drop for SendFuture<'a, S, R, T> {
+    fn drop(&mut self) {
+        if let Some(half) = &mut self.sender.half {
+            half.cancel_wait();
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> RecvFuture<'a, S, R, T> {
+    pub(super) fn new(receiver: &'a mut Receiver<S, R, T>) -> Self {
+        RecvFuture {
+            receiver,
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> Future for RecvFuture<'a, S, R, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        let half = match &mut self.get_mut().receiver.half {
+            Some(half) => half,
+            None => return Poll::Ready(None),
+        };
+
+        match half.poll_advance((), cx.waker()) {
+            Ok(msg) => Poll::Ready(Some(msg)),
+            Err(AdvanceError::BufferFull(())) => Poll::Pending,
+            Err(_) => Poll::Ready(None),
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> Drop for RecvFuture<'a, S, R, T> {
+    fn drop(&mut self) {
+        if let Some(half) = &mut self.receiver.half {
+            half.cancel_wait();
+        }
+    }
+}
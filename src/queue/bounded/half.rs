@@ -3,11 +3,20 @@ use std::cell::Cell;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::ops::Drop;
 use std::mem::{transmute_copy, ManuallyDrop};
+use std::task::Waker;
+use std::time::Instant;
+use std::cmp;
+use std::iter::Peekable;
 
 use role::Role;
-use counter::{AtomicCounter, COUNTER_VALID_RANGE};
+use counter::{Counter, CounterRange, AtomicCounter, COUNTER_VALID_RANGE};
 use buffer::{Buffer, BufRange};
 use sequence::{Sequence, Limit, CacheError, CommitError};
+use sequence::owned::Owned;
+use queue::waiters::{WaitList, Key};
+use queue::park::Parker;
+
+use super::head::Skipped;
 
 pub(crate) trait HeadHalf: Limit + Clone {
     type Seq: Sequence;
@@ -16,6 +25,38 @@ pub(crate) trait HeadHalf: Limit + Clone {
     fn seq(&self) -> &Self::Seq;
     fn amount(&self) -> &AtomicUsize;
     fn close_counter(&self) -> &AtomicCounter;
+    fn close(&self);
+    fn waiters(&self) -> &WaitList<Waker>;
+
+    /// Whether the channel is closed by the counterpart side.
+    fn is_disconnected(&self) -> bool;
+
+    /// Whether the limit can't advance anymore once the channel is closed.
+    ///
+    /// Halves gated by others should wait for them to catch up.
+    fn is_limit_final(&self) -> bool {
+        true
+    }
+
+    /// Wake up a counterpart waiting for this half to advance.
+    /// Returns `false` if no waiter was found.
+    fn notify_counterpart(&self) -> bool {
+        self.waiters().notify(Self::Role::KIND.counterpart())
+    }
+
+    /// Abandon claimed slots which can't be given back, so the counterpart
+    /// steps over them. They should be committed after this call.
+    ///
+    /// Returns `false` if the counterpart can't step over them,
+    /// and the channel should be closed instead.
+    fn skip(&self, _range: CounterRange) -> bool {
+        false
+    }
+
+    /// Slots abandoned by the counterpart, which this half should step over.
+    fn skipped(&self) -> Option<&Skipped> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -28,12 +69,15 @@ pub(crate) struct Half<B, H, T> where
     head: H,
     cache: <H::Seq as Sequence>::Cache,
     closed_cache: Cell<bool>,
+    wait_key: Option<Key>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum AdvanceError<T> {
     BufferFull(T),
     Closed(T),
+    /// Closed by the counterpart side, like when every counterpart halves are dropped.
+    Disconnected(T),
 }
 
 impl<T> AdvanceError<T> {
@@ -41,6 +85,15 @@ impl<T> AdvanceError<T> {
         match self {
             AdvanceError::BufferFull(v) => v,
             AdvanceError::Closed(v) => v,
+            AdvanceError::Disconnected(v) => v,
+        }
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> AdvanceError<U> {
+        match self {
+            AdvanceError::BufferFull(v) => AdvanceError::BufferFull(f(v)),
+            AdvanceError::Closed(v) => AdvanceError::Closed(f(v)),
+            AdvanceError::Disconnected(v) => AdvanceError::Disconnected(f(v)),
         }
     }
 }
@@ -65,26 +118,43 @@ impl<B, H, T> Half<B, H, T> where
         Ok(Half {
             cache: head.seq().cache(&head)?,
             closed_cache: false.into(),
+            wait_key: None,
             buf,
             head,
         })
     }
 
+    pub fn buf(&self) -> &Buffer<B, T> {
+        &self.buf
+    }
+
+    pub fn head(&self) -> &H {
+        &self.head
+    }
+
     pub fn try_clone(&self) -> Option<Self> {
         Half::new(self.buf.clone(), self.head.clone()).ok()
     }
 
+    /// Whether the channel is closed.
+    ///
+    /// Closed channel may still have messages to receive,
+    /// so it's not cached unless this half can't advance anymore.
     pub fn is_closed(&self) -> bool {
-        if self.closed_cache.get() {
-            return true;
-        }
+        self.closed_cache.get() || self.head.close_counter().fetch().is_err()
+    }
 
-        if self.head.close_counter().fetch().is_err() {
-            self.closed_cache.set(true);
-            return true;
-        }
+    pub fn is_disconnected(&self) -> bool {
+        self.head.is_disconnected()
+    }
 
-        false
+    /// Error to report that the channel is closed, telling which side closed it.
+    fn closed<X>(&self, v: X) -> AdvanceError<X> {
+        if self.head.is_disconnected() {
+            AdvanceError::Disconnected(v)
+        } else {
+            AdvanceError::Closed(v)
+        }
     }
 
     pub fn close(&mut self) {
@@ -93,38 +163,462 @@ impl<B, H, T> Half<B, H, T> where
         }
 
         self.closed_cache.set(true);
-        self.head.close_counter().close();
+        self.head.close();
     }
 
     pub fn try_advance(&mut self, input: Input<H>) -> Result<Output<H>, AdvanceError<Input<H>>> {
+        let count = loop {
+            let count = match self.claim() {
+                Ok(count) => count,
+                Err(err) => return Err(err.map(|()| input)),
+            };
+
+            if !self.head.skipped().is_some_and(|skipped| skipped.take(count)) {
+                break count;
+            }
+
+            // Abandoned by the counterpart. Step over it
+            if let Err(CommitError) = self.head.seq().commit(&mut self.cache, count) {
+                self.closed_cache.set(true);
+                return Err(self.closed(input));
+            }
+        };
+
+        let buffer = self.buf.get(count);
+        let (backup, res) = unsafe {(
+            ManuallyDrop::new(transmute_copy::<Input<H>, Input<H>>(&input)),
+            H::Role::interact(buffer, input),
+        )};
+
+        match self.head.seq().commit(&mut self.cache, count) {
+            Ok(()) => {
+                self.head.notify_counterpart();
+                Ok(res)
+            }
+            Err(CommitError) => {
+                self.closed_cache.set(true);
+                let input = ManuallyDrop::into_inner(backup);
+                Err(self.closed(input))
+            }
+        }
+    }
+
+    fn claim(&mut self) -> Result<Counter, AdvanceError<()>> {
         if self.closed_cache.get() {
-            return Err(AdvanceError::Closed(input));
+            return Err(self.closed(()));
         }
 
-        match self.head.seq().claim(&mut self.cache, &self.head) {
-            None => {
-                if self.head.close_counter().fetch().is_err() {
-                    Err(AdvanceError::Closed(input))
-                } else {
-                    Err(AdvanceError::BufferFull(input))
+        let claimed = match self.head.seq().claim(&mut self.cache, &self.head) {
+            None if self.head.close_counter().fetch().is_err() && self.head.is_limit_final() => {
+                // Counterpart may advanced right before it's closed. Check it again
+                self.head.seq().claim(&mut self.cache, &self.head)
+            }
+            None => return Err(AdvanceError::BufferFull(())),
+            claimed => claimed,
+        };
+
+        claimed.ok_or_else(|| self.closed(()))
+    }
+
+    /// Claim up to `max` slots at once.
+    ///
+    /// Returned batch commits its processed slots at once when it's finished or dropped.
+    pub fn try_claim_many(&mut self, max: usize) -> Result<Batch<'_, B, H, T>, AdvanceError<()>> {
+        let max = cmp::min(max, self.buf.capacity());
+
+        let range = loop {
+            let range = self.claim_n(max)?;
+            let mut start = range.start;
+
+            // Step over leading slots abandoned by the counterpart,
+            // so the batch never starts with them
+            if let Some(skipped) = self.head.skipped() {
+                while start != range.end && skipped.take(start) {
+                    start += 1;
                 }
             }
-            Some(count) => {
-                let buffer = self.buf.get(count);
-                let (backup, res) = unsafe {(
-                    ManuallyDrop::new(transmute_copy::<Input<H>, Input<H>>(&input)),
-                    H::Role::interact(buffer, input),
-                )};
-
-                match self.head.seq().commit(&mut self.cache, count) {
-                    Ok(()) => Ok(res),
-                    Err(CommitError) => {
-                        self.closed_cache.set(true);
-                        let input = ManuallyDrop::into_inner(backup);
-                        Err(AdvanceError::Closed(input))
+
+            if start != range.start {
+                if let Err(CommitError) =
+                    self.head.seq().commit_n(&mut self.cache, Counter::range(range.start, start))
+                {
+                    self.closed_cache.set(true);
+                    return Err(self.closed(()));
+                }
+            }
+
+            // Claim again if every claimed slots are abandoned
+            if start != range.end || range.len() == 0 {
+                break Counter::range(start, range.end);
+            }
+        };
+
+        Ok(Batch {
+            done: range.start,
+            range,
+            half: self,
+        })
+    }
+
+    fn claim_n(&mut self, max: usize) -> Result<CounterRange, AdvanceError<()>> {
+        if self.closed_cache.get() {
+            return Err(self.closed(()));
+        }
+
+        let claimed = match self.head.seq().claim_n(&mut self.cache, &self.head, max) {
+            None if self.head.close_counter().fetch().is_err() && self.head.is_limit_final() => {
+                // Counterpart may advanced right before it's closed. Check it again
+                self.head.seq().claim_n(&mut self.cache, &self.head, max)
+            }
+            None => return Err(AdvanceError::BufferFull(())),
+            claimed => claimed,
+        };
+
+        claimed.ok_or_else(|| self.closed(()))
+    }
+
+    /// Advance this half by inputs from `inputs` until it's exhausted or this half fails.
+    ///
+    /// Slots are claimed in chunks, sized by the lower bound of the iterator's size hint,
+    /// and each chunk is committed at once. The iterator is not trusted about its length,
+    /// so claimed slots left unused are given back. Outputs are passed to `output` in order.
+    /// Returns the number of advanced slots.
+    pub fn try_advance_many<I, F>(&mut self, inputs: &mut Peekable<I>, mut output: F) -> usize where
+        I: Iterator<Item=Input<H>>,
+        F: FnMut(Output<H>),
+    {
+        let mut advanced = 0;
+
+        while inputs.peek().is_some() {
+            let max = cmp::max(inputs.size_hint().0, 1);
+
+            let mut batch = match self.try_claim_many(max) {
+                Ok(batch) => batch,
+                Err(_) => break,
+            };
+
+            while batch.remaining() != 0 {
+                match inputs.next() {
+                    Some(input) => output(batch.advance(input)),
+                    None => break,
+                }
+            }
+
+            match batch.finish() {
+                Ok(len) => advanced += len,
+                Err(_) => break,
+            }
+        }
+
+        advanced
+    }
+
+    /// Advance this half, blocking current thread while the buffer is full.
+    ///
+    /// If deadline is given and reached, returns `AdvanceError::BufferFull`.
+    pub fn advance(
+        &mut self, mut input: Input<H>, deadline: Option<Instant>
+    ) -> Result<Output<H>, AdvanceError<Input<H>>> {
+        let kind = H::Role::KIND;
+        let parker = Parker::new();
+
+        loop {
+            match self.try_advance(input) {
+                Err(AdvanceError::BufferFull(v)) => input = v,
+                res => return res,
+            }
+
+            let key = self.head.waiters().wait(kind, parker.waker());
+
+            // Recheck as the counterpart may advanced before registration
+            match self.try_advance(input) {
+                Err(AdvanceError::BufferFull(v)) => input = v,
+                res => {
+                    if !self.head.waiters().cancel(key) {
+                        // Already notified. Pass it to other waiter
+                        self.head.waiters().notify(kind);
                     }
+                    return res;
                 }
             }
+
+            match deadline {
+                None => parker.park(),
+                Some(deadline) => if !parker.park_until(deadline) {
+                    if !self.head.waiters().cancel(key) {
+                        self.head.waiters().notify(kind);
+                    }
+                    // Last chance before timeout
+                    return self.try_advance(input);
+                }
+            }
+        }
+    }
+
+    /// Try to advance this half, or register given waker if the buffer is full.
+    ///
+    /// If this returns `AdvanceError::BufferFull`, the waker will be woken
+    /// when the counterpart advances or the channel is closed.
+    /// Waker registered by previous call is replaced.
+    pub fn poll_advance(
+        &mut self, input: Input<H>, waker: &Waker
+    ) -> Result<Output<H>, AdvanceError<Input<H>>> {
+        if let Some(key) = self.wait_key.take() {
+            // We're retrying now, so it's ok to consume notification if any
+            self.head.waiters().cancel(key);
+        }
+
+        let input = match self.try_advance(input) {
+            Err(AdvanceError::BufferFull(v)) => v,
+            res => return res,
+        };
+
+        let key = self.head.waiters().wait(H::Role::KIND, waker.clone());
+
+        // Recheck as the counterpart may advanced before registration
+        match self.try_advance(input) {
+            Err(AdvanceError::BufferFull(v)) => {
+                self.wait_key = Some(key);
+                Err(AdvanceError::BufferFull(v))
+            }
+            res => {
+                self.wait_key = Some(key);
+                self.cancel_wait();
+                res
+            }
+        }
+    }
+
+    /// Deregister waker registered by `poll_advance`.
+    ///
+    /// If it's already notified, the notification is passed to other waiter
+    /// so it's not lost by cancelled task.
+    pub fn cancel_wait(&mut self) {
+        if let Some(key) = self.wait_key.take() {
+            if !self.head.waiters().cancel(key) {
+                self.head.waiters().notify(H::Role::KIND);
+            }
+        }
+    }
+}
+
+impl<B, H, T> Half<B, H, T> where
+    B: BufRange,
+    H: HeadHalf<Seq=Owned>,
+    H::Role: Role<Item=T>,
+{
+    /// Returns the slot which will be processed next, without claiming it.
+    ///
+    /// Nobody else can claim it as the sequence is owned by this half,
+    /// so it's valid until this half advances.
+    pub fn peek(&self) -> Result<*mut T, AdvanceError<()>> {
+        if self.closed_cache.get() {
+            return Err(self.closed(()));
+        }
+
+        let count = match self.head.seq().peek(&self.cache, &self.head) {
+            None if self.head.close_counter().fetch().is_err() && self.head.is_limit_final() => {
+                // Counterpart may advanced right before it's closed. Check it again
+                self.head.seq().peek(&self.cache, &self.head)
+            }
+            None => return Err(AdvanceError::BufferFull(())),
+            count => count,
+        };
+
+        let mut count = match count {
+            None => return Err(self.closed(())),
+            Some(count) => count,
+        };
+
+        // Step over slots abandoned by the counterpart, which are committed on next advance
+        if let Some(skipped) = self.head.skipped() {
+            while skipped.contains(count) {
+                count += 1;
+
+                if count == self.head.count() {
+                    return Err(match self.head.close_counter().fetch() {
+                        Err(_) if self.head.is_limit_final() => self.closed(()),
+                        _ => AdvanceError::BufferFull(()),
+                    });
+                }
+            }
+        }
+
+        Ok(self.buf.get(count))
+    }
+}
+
+/// Claimed range of slots which is being processed.
+///
+/// Processed slots are committed at once when it's finished or dropped, even on panic.
+/// Unprocessed slots are given back. If it's not possible as other halves claimed
+/// after them, they're committed as abandoned slots which the counterpart steps over,
+/// or the channel is closed if the counterpart can't do it.
+pub(crate) struct Batch<'a, B, H, T> where
+    B: BufRange + 'a,
+    H: HeadHalf + 'a,
+    H::Role: Role<Item=T>,
+    T: 'a,
+{
+    half: &'a mut Half<B, H, T>,
+    range: CounterRange,
+    done: Counter,
+}
+
+impl<'a, B, H, T> Batch<'a, B, H, T> where
+    B: BufRange,
+    H: HeadHalf,
+    H::Role: Role<Item=T>,
+{
+    /// Number of claimed slots not processed yet.
+    ///
+    /// Slots abandoned by the counterpart are not counted, as they're stepped over.
+    pub fn remaining(&self) -> usize {
+        let rest = Counter::range(self.done, self.range.end);
+        rest.len() - self.half.head.skipped().map_or(0, |skipped| skipped.count_in(rest))
+    }
+
+    /// Step over slots abandoned by the counterpart, so the next slot is not one of them.
+    fn step_over_skipped(&mut self) {
+        if let Some(skipped) = self.half.head.skipped() {
+            while self.done != self.range.end && skipped.take(self.done) {
+                self.done += 1;
+            }
+        }
+    }
+
+    /// Process next claimed slot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if every claimed slots are processed.
+    pub fn advance(&mut self, input: Input<H>) -> Output<H> {
+        self.advance_as::<H::Role>(input)
+    }
+
+    /// Process next claimed slot with another role of the same kind.
+    ///
+    /// # Panics
+    ///
+    /// Panics if every claimed slots are processed.
+    pub fn advance_as<R: Role<Item=T>>(&mut self, input: R::Input) -> R::Output {
+        debug_assert_eq!(R::KIND, H::Role::KIND);
+        assert!(self.remaining() != 0, "No more claimed slot in this batch");
+
+        let count = self.done;
+        self.done = count + 1;
+
+        let output = unsafe {
+            R::interact(self.half.buf.get(count), input)
+        };
+        self.step_over_skipped();
+        output
+    }
+
+    /// Pointer to the next claimed slot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if every claimed slots are processed.
+    pub fn next_slot(&self) -> *mut T {
+        assert!(self.remaining() != 0, "No more claimed slot in this batch");
+
+        self.half.buf.get(self.done)
+    }
+
+    /// Mark next claimed slot as processed without interacting with it.
+    ///
+    /// # Safety
+    ///
+    /// Caller should process the slot by itself via `next_slot`,
+    /// as if `Role::interact` is called on it.
+    pub unsafe fn skip(&mut self) {
+        assert!(self.remaining() != 0, "No more claimed slot in this batch");
+
+        self.done += 1;
+        self.step_over_skipped();
+    }
+
+    /// Commit processed slots and give back the rest.
+    /// Returns the number of committed slots.
+    pub fn finish(mut self) -> Result<usize, AdvanceError<()>> {
+        self.settle()
+    }
+
+    /// Same as `finish`, but processed slots are passed to `revoke`
+    /// instead of `Role::revoke` if the channel is closed before commit.
+    pub fn finish_with<F: FnMut(*mut T)>(mut self, revoke: F) -> Result<usize, AdvanceError<()>> {
+        self.settle_with(revoke)
+    }
+
+    fn settle(&mut self) -> Result<usize, AdvanceError<()>> {
+        self.settle_with(|slot| unsafe { H::Role::revoke(slot) })
+    }
+
+    fn settle_with<F: FnMut(*mut T)>(&mut self, revoke: F) -> Result<usize, AdvanceError<()>> {
+        let res = self.commit_with(revoke);
+        let rest = Counter::range(self.done, self.range.end);
+        // Prevent to be settled twice
+        self.range.start = self.range.end;
+
+        let half = &mut *self.half;
+
+        if rest.len() != 0 && half.head.seq().revert(&mut half.cache, rest).is_err() {
+            // Other halves claimed after them. Leave them to be stepped over if possible
+            if half.head.skip(rest) {
+                let _ = half.head.seq().commit_n(&mut half.cache, rest);
+            } else {
+                half.close();
+            }
+        }
+
+        res
+    }
+
+    /// Commit processed slots so far, and keep the rest claimed.
+    /// Returns the number of committed slots.
+    pub fn commit(&mut self) -> Result<usize, AdvanceError<()>> {
+        self.commit_with(|slot| unsafe { H::Role::revoke(slot) })
+    }
+
+    fn commit_with<F: FnMut(*mut T)>(&mut self, mut revoke: F) -> Result<usize, AdvanceError<()>> {
+        let done = Counter::range(self.range.start, self.done);
+        self.range.start = self.done;
+
+        if done.len() == 0 {
+            return Ok(0);
+        }
+
+        let half = &mut *self.half;
+
+        match half.head.seq().commit_n(&mut half.cache, done) {
+            Ok(()) => {
+                for _ in done {
+                    if !half.head.notify_counterpart() {
+                        break;
+                    }
+                }
+                Ok(done.len())
+            }
+            Err(CommitError) => {
+                half.closed_cache.set(true);
+                for count in done {
+                    revoke(half.buf.get(count));
+                }
+                Err(half.closed(()))
+            }
+        }
+    }
+}
+
+impl<'a, B, H, T> Drop for Batch<'a, B, H, T> where
+    B: BufRange,
+    H: HeadHalf,
+    H::Role: Role<Item=T>,
+{
+    fn drop(&mut self) {
+        if self.range.len() != 0 {
+            let _ = self.settle();
         }
     }
 }
@@ -135,6 +629,8 @@ impl<B, H, T> Drop for Half<B, H, T> where
     H::Role: Role<Item=T>,
 {
     fn drop(&mut self) {
+        self.cancel_wait();
+
         let ref_count = self.head.amount().fetch_sub(1, Ordering::Release);
 
         if ref_count == 1 {
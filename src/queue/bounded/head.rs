@@ -1,14 +1,20 @@
 
 use std::sync::Arc;
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering};
 use std::marker::PhantomData;
+use std::cmp;
+use std::task::Waker;
+use std::fmt;
 
-use role;
+use role::{self, Role, Kind};
 use counter::{Counter, CounterRange, AtomicCounter};
-use sequence::{Sequence, Limit};
-use buffer::BufRange;
+use sequence::{Sequence, Limit, Offset, CacheError};
+use sequence::owned::Owned;
+use buffer::{Buffer, BufRange};
+use queue::waiters::WaitList;
+use queue::lock::SpinLock;
 
-use super::half::{Half, HeadHalf};
+use super::half::{Half, HeadHalf, Batch};
 
 #[derive(Debug)]
 pub(crate) struct Head<S: Sequence, R: Sequence> {
@@ -16,12 +22,41 @@ pub(crate) struct Head<S: Sequence, R: Sequence> {
     receiver: R,
     sender_count: AtomicUsize,
     receiver_count: AtomicUsize,
+    /// Whether senders closed the channel, like by dropping every senders.
+    senders_closed: AtomicBool,
+    /// Whether receivers closed the channel, like by dropping every receivers.
+    receivers_closed: AtomicBool,
+    waiters: WaitList<Waker>,
+    /// Pipeline stages which should process messages before receivers.
+    gates: Vec<Arc<Gate>>,
+    skipped: Skipped,
+}
+
+/// Slots abandoned by senders, which stages and receivers step over.
+///
+/// Shared sender can't give back claimed slots once other senders claimed after them,
+/// so it commits them without messages after registering them here.
+pub(crate) struct Skipped {
+    /// Length of the list, to skip locking it while nothing is abandoned.
+    len: AtomicUsize,
+    list: SpinLock<Vec<Counter>>,
+}
+
+/// Sequence of a pipeline stage.
+///
+/// It's gated by the stages given as `after`, or by the sender if none.
+#[derive(Debug)]
+pub(crate) struct Gate {
+    seq: Owned,
+    after: Vec<Arc<Gate>>,
+    detached: AtomicBool,
 }
 
 #[derive(Debug)]
 pub(crate) struct SenderHead<S: Sequence, R: Sequence, T> {
     head: Arc<Head<S, R>>,
-    capacity: usize,
+    /// To track capacity, as the buffer may grow.
+    buf: Buffer<Arc<Head<S, R>>, T>,
     role: PhantomData<role::Send<T>>,
 }
 
@@ -32,22 +67,208 @@ pub(crate) struct ReceiverHead<S: Sequence, R: Sequence, T> {
 }
 
 pub(crate) type SenderHalf<S, R, T> = Half<Arc<Head<S, R>>, SenderHead<S, R, T>, T>;
+pub(crate) type SenderBatch<'a, S, R, T> = Batch<'a, Arc<Head<S, R>>, SenderHead<S, R, T>, T>;
 pub(crate) type ReceiverHalf<S, R, T> = Half<Arc<Head<S, R>>, ReceiverHead<S, R, T>, T>;
+pub(crate) type ReceiverBatch<'a, S, R, T> = Batch<'a, Arc<Head<S, R>>, ReceiverHead<S, R, T>, T>;
 
 impl<S: Sequence, R: Sequence> Head<S, R> {
     pub fn new(sender: S, receiver: R) -> Arc<Self> {
+        Head::with_gates(sender, receiver, Vec::new())
+    }
+
+    /// Create a head whose receivers are gated by given pipeline stages.
+    pub fn with_gates(sender: S, receiver: R, gates: Vec<Arc<Gate>>) -> Arc<Self> {
         Arc::new(Head {
             sender,
             receiver,
             sender_count: 0.into(),
             receiver_count: 0.into(),
+            senders_closed: false.into(),
+            receivers_closed: false.into(),
+            waiters: WaitList::new(),
+            gates,
+            skipped: Skipped::new(),
         })
     }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+
+    pub fn waiters(&self) -> &WaitList<Waker> {
+        &self.waiters
+    }
+
+    pub fn skipped(&self) -> &Skipped {
+        &self.skipped
+    }
+
+    /// Number of messages sent but not received yet.
+    ///
+    /// Receiver counter is loaded first, so it never passes the sender counter.
+    /// But it may be stale while the sender advances, so this can exceed the capacity.
+    pub fn len(&self) -> usize {
+        let receiver_last = self.receiver.fetch_last();
+        let sender_last = self.sender.fetch_last();
+
+        (sender_last - receiver_last) as usize
+    }
+
+    pub fn sender_count(&self) -> usize {
+        self.sender_count.load(Ordering::Acquire)
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.receiver_count.load(Ordering::Acquire)
+    }
 }
 
 impl<S: Sequence, R: Sequence> Head<S, R> {
-    pub fn close(&self) {
+    /// Close the channel from given side and wake up every blocked halves.
+    ///
+    /// Both sides share the sender counter as a closed flag, as closing
+    /// the receiver counter may drop messages twice on commit failure.
+    pub fn close(&self, kind: Kind) {
+        self.side_closed(kind).store(true, Ordering::Release);
         self.sender.counter().close();
+        self.waiters.notify_all();
+    }
+
+    /// Whether the channel is closed from given side.
+    pub fn is_closed_by(&self, kind: Kind) -> bool {
+        self.side_closed(kind).load(Ordering::Acquire)
+    }
+
+    fn side_closed(&self, kind: Kind) -> &AtomicBool {
+        match kind {
+            Kind::Send => &self.senders_closed,
+            Kind::Receive => &self.receivers_closed,
+        }
+    }
+}
+
+impl Skipped {
+    fn new() -> Self {
+        Skipped {
+            len: 0.into(),
+            list: SpinLock::new(Vec::new()),
+        }
+    }
+
+    /// Mark given slots as abandoned. They should be committed after this call.
+    pub fn insert(&self, range: CounterRange) {
+        let mut list = self.list.lock();
+        list.extend(range);
+        self.len.store(list.len(), Ordering::Release);
+    }
+
+    /// Whether given slot is abandoned.
+    ///
+    /// Caller should have observed the commit of the slot.
+    pub fn contains(&self, count: Counter) -> bool {
+        self.len.load(Ordering::Acquire) != 0 && self.list.lock().contains(&count)
+    }
+
+    /// Number of abandoned slots in given range.
+    pub fn count_in(&self, range: CounterRange) -> usize {
+        if range.len() == 0 || self.len.load(Ordering::Acquire) == 0 {
+            return 0;
+        }
+
+        self.list.lock().iter()
+            .filter(|&&count| count >= range.start && count < range.end)
+            .count()
+    }
+
+    /// Remove the mark of given slot. Returns `false` if it's not abandoned.
+    pub fn take(&self, count: Counter) -> bool {
+        if self.len.load(Ordering::Acquire) == 0 {
+            return false;
+        }
+
+        let mut list = self.list.lock();
+        match list.iter().position(|&c| c == count) {
+            Some(idx) => {
+                list.swap_remove(idx);
+                self.len.store(list.len(), Ordering::Release);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Skipped")
+            .field("len", &self.len.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl Gate {
+    pub fn new(after: Vec<Arc<Gate>>) -> Self {
+        Gate {
+            seq: Owned::default(),
+            after,
+            detached: false.into(),
+        }
+    }
+
+    pub fn seq(&self) -> &Owned {
+        &self.seq
+    }
+
+    pub fn after(&self) -> &[Arc<Gate>] {
+        &self.after
+    }
+
+    /// Detach this stage from the pipeline, so it doesn't gate others anymore.
+    pub fn detach(&self) {
+        self.detached.store(true, Ordering::Release);
+    }
+
+    /// Last count processed by this stage. Detached stage passes its own limit through.
+    fn fetch_last<S: Sequence>(&self, sender: &S) -> Counter {
+        if self.detached.load(Ordering::Acquire) {
+            Gate::limit(&self.after, sender)
+        } else {
+            self.seq.fetch_last()
+        }
+    }
+
+    /// Count that every given stages have processed, or the sender's if none.
+    pub fn limit<S: Sequence>(gates: &[Arc<Gate>], sender: &S) -> Counter {
+        let mut gates = gates.iter().map(|gate| gate.fetch_last(sender));
+
+        match gates.next() {
+            None => sender.fetch_last(),
+            Some(first) => gates.fold(first, |min, count| if count < min { count } else { min }),
+        }
+    }
+}
+
+/// Introspection of the channel, shared by senders and receivers.
+impl<S: Sequence, R: Sequence, T> Buffer<Arc<Head<S, R>>, T> {
+    /// Number of messages in the buffer, which never exceeds the capacity.
+    pub fn len(&self) -> usize {
+        cmp::min(self.head().len(), self.capacity())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    pub fn sender_count(&self) -> usize {
+        self.head().sender_count()
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.head().receiver_count()
     }
 }
 
@@ -58,16 +279,66 @@ impl<S: Sequence, R: Sequence> BufRange for Arc<Head<S, R>> {
 
         Counter::range(receiver_last, sender_last)
     }
+
+    fn is_skipped(&self, count: Counter) -> bool {
+        self.skipped.contains(count)
+    }
 }
 
 impl<S: Sequence, R: Sequence, T> SenderHead<S, R, T> {
-    pub fn new(head: Arc<Head<S, R>>, capacity: usize) -> Self {
+    pub fn new(buf: Buffer<Arc<Head<S, R>>, T>) -> Self {
         SenderHead {
-            head,
-            capacity,
+            head: buf.head().clone(),
+            buf,
             role: PhantomData,
         }
     }
+
+    /// Create a receiver cache which the sender uses to evict messages.
+    ///
+    /// It's not counted as a receiver, so the channel is still closed
+    /// when every receivers are dropped.
+    pub fn evict_cache(&self) -> Result<R::Cache, CacheError> {
+        self.head.receiver.cache(&ReceiverLimit(&self.head))
+    }
+
+    /// Take the oldest message out of the buffer to make room for new one.
+    ///
+    /// Returns `None` if the buffer is empty, as receivers may took it first.
+    pub fn evict(&self, buf: &Buffer<Arc<Head<S, R>>, T>, cache: &mut R::Cache) -> Option<T> {
+        let receiver = &self.head.receiver;
+
+        loop {
+            let count = receiver.claim(cache, &ReceiverLimit(&self.head))?;
+            let msg = if self.head.skipped.take(count) {
+                None
+            } else {
+                Some(unsafe { role::Receive::interact(buf.get(count), ()) })
+            };
+
+            // Receiver sequence is never closed
+            let _ = receiver.commit(cache, count);
+
+            if msg.is_some() {
+                return msg;
+            }
+        }
+    }
+}
+
+impl<R: Sequence, T> SenderHead<Owned, R, T> {
+    /// Grow the buffer to given capacity. Messages sent after this call are stored
+    /// in the new storage, and older ones stay in the current storage.
+    ///
+    /// Owned sender has no other senders which may write to the current storage,
+    /// so the caller should ensure that every its claimed slots are committed or given back.
+    pub unsafe fn grow(&self, capacity: usize) {
+        let start = self.head.sender.fetch_last();
+
+        // Slots before the receiver counter are never accessed again
+        self.buf.release(self.head.receiver.fetch_last());
+        self.buf.grow(capacity, start);
+    }
 }
 
 impl<S: Sequence, R: Sequence, T> HeadHalf for SenderHead<S, R, T> {
@@ -85,11 +356,38 @@ impl<S: Sequence, R: Sequence, T> HeadHalf for SenderHead<S, R, T> {
     fn close_counter(&self) -> &AtomicCounter {
         self.head.sender.counter()
     }
+
+    fn close(&self) {
+        self.head.close(Kind::Send)
+    }
+
+    fn waiters(&self) -> &WaitList<Waker> {
+        &self.head.waiters
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.head.is_closed_by(Kind::Receive)
+    }
+
+    fn skip(&self, range: CounterRange) -> bool {
+        self.head.skipped.insert(range);
+        true
+    }
+
+    fn notify_counterpart(&self) -> bool {
+        if self.head.gates.is_empty() {
+            self.head.waiters.notify(Kind::Receive)
+        } else {
+            // Stages and receivers wait together, and single notification may not
+            // reach the one which can process the message.
+            self.head.waiters.notify_every(Kind::Receive)
+        }
+    }
 }
 
 impl<S: Sequence, R: Sequence, T> Limit for SenderHead<S, R, T> {
     fn count(&self) -> Counter {
-        self.head.receiver.fetch_last() + self.capacity
+        Offset(self.head.receiver.counter(), self.buf.capacity()).count()
     }
 }
 
@@ -97,7 +395,7 @@ impl<S: Sequence, R: Sequence, T> Clone for SenderHead<S, R, T> {
     fn clone(&self) -> Self {
         SenderHead {
             head: Arc::clone(&self.head),
-            capacity: self.capacity,
+            buf: self.buf.clone(),
             role: PhantomData,
         }
     }
@@ -127,11 +425,46 @@ impl<S: Sequence, R: Sequence, T> HeadHalf for ReceiverHead<S, R, T> {
     fn close_counter(&self) -> &AtomicCounter {
         self.head.sender.counter()
     }
+
+    fn close(&self) {
+        self.head.close(Kind::Receive)
+    }
+
+    fn waiters(&self) -> &WaitList<Waker> {
+        &self.head.waiters
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.head.is_closed_by(Kind::Send)
+    }
+
+    fn skipped(&self) -> Option<&Skipped> {
+        // Owned sender never abandons slots. Skip checking them on every receive
+        if S::MAY_ABANDON {
+            Some(&self.head.skipped)
+        } else {
+            None
+        }
+    }
+
+    fn is_limit_final(&self) -> bool {
+        // Stages may still process messages sent before close
+        self.head.gates.is_empty() || self.count() == self.head.sender.fetch_last()
+    }
 }
 
 impl<S: Sequence, R: Sequence, T> Limit for ReceiverHead<S, R, T> {
     fn count(&self) -> Counter {
-        self.head.sender.fetch_last()
+        Gate::limit(&self.head.gates, &self.head.sender)
+    }
+}
+
+/// Limit of the receiver sequence, without the role of `ReceiverHead`.
+struct ReceiverLimit<'a, S: Sequence + 'a, R: Sequence + 'a>(&'a Head<S, R>);
+
+impl<'a, S: Sequence, R: Sequence> Limit for ReceiverLimit<'a, S, R> {
+    fn count(&self) -> Counter {
+        Gate::limit(&self.0.gates, &self.0.sender)
     }
 }
 
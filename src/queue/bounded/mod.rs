@@ -1,9 +1,28 @@
 
-use sequence::{Sequence, MultiCache};
-use buffer::Buffer;
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+use std::task::Waker;
+use std::cmp;
+use std::iter::Peekable;
 
-mod half;
-mod head;
+use sequence::{Sequence, MultiCache, Evict};
+use sequence::owned::Owned;
+use sequence::shared::Evictable;
+use buffer::{self, Buffer};
+use queue::waiters::WaitList;
+
+pub(crate) mod half;
+pub(crate) mod head;
+mod future;
+mod drain;
+mod slot;
+#[cfg(feature = "futures")]
+mod stream;
+
+pub use self::future::{SendFuture, RecvFuture};
+pub use self::drain::Drain;
+pub use self::slot::{SendSlot, RecvGuard};
+pub use error::{SendError, RecvError};
 
 use self::half::{Half, AdvanceError};
 use self::head::{Head, SenderHead, SenderHalf, ReceiverHead, ReceiverHalf};
@@ -11,49 +30,127 @@ use self::head::{Head, SenderHead, SenderHalf, ReceiverHead, ReceiverHalf};
 #[derive(Debug)]
 pub struct Sender<S: Sequence, R: Sequence, T> {
     half: Option<SenderHalf<S, R, T>>,
+    buf: Buffer<Arc<Head<S, R>>, T>,
+    /// Receiver cache to evict messages with, created on first `force_send`.
+    evict_cache: Option<R::Cache>,
+    /// Set by `grow_when_full`.
+    auto_grow: Option<AutoGrow<S, R, T>>,
+    /// Message accepted by `Sink::start_send` but not yet sent.
+    #[cfg(feature = "futures")]
+    pending: Option<T>,
+}
+
+/// Policy to grow the buffer when it's been full for a while.
+#[derive(Debug)]
+struct AutoGrow<S: Sequence, R: Sequence, T> {
+    period: Duration,
+    max_capacity: usize,
+    /// When sends started to find the buffer full. Reset on successful send.
+    full_since: Option<Instant>,
+    /// `SenderHead::grow`, which is only available for owned sender.
+    grow: unsafe fn(&SenderHead<S, R, T>, usize),
 }
 
 #[derive(Debug)]
 pub struct Receiver<S: Sequence, R: Sequence, T> {
     half: Option<ReceiverHalf<S, R, T>>,
+    buf: Buffer<Arc<Head<S, R>>, T>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum SendError<T> {
-    BufferFull(T),
-    Closed(T),
-}
+// Sound as the channel never creates `Pin` to its fields or messages. Messages are
+// moved in and out by value, so halves and futures borrowing them can be moved freely
+// even if `T: !Unpin`.
+impl<S: Sequence, R: Sequence, T> Unpin for Sender<S, R, T> {}
+impl<S: Sequence, R: Sequence, T> Unpin for Receiver<S, R, T> {}
 
 impl<T> From<AdvanceError<T>> for SendError<T> {
     fn from(e: AdvanceError<T>) -> Self {
         match e {
             AdvanceError::BufferFull(v) => SendError::BufferFull(v),
             AdvanceError::Closed(v) => SendError::Closed(v),
+            AdvanceError::Disconnected(v) => SendError::Disconnected(v),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
-pub struct RecvError;
+pub enum SendTimeoutError<T> {
+    Timeout(T),
+    Closed(T),
+    /// Every receivers are dropped or closed the channel.
+    Disconnected(T),
+}
+
+impl<T> From<AdvanceError<T>> for SendTimeoutError<T> {
+    fn from(e: AdvanceError<T>) -> Self {
+        match e {
+            AdvanceError::BufferFull(v) => SendTimeoutError::Timeout(v),
+            AdvanceError::Closed(v) => SendTimeoutError::Closed(v),
+            AdvanceError::Disconnected(v) => SendTimeoutError::Disconnected(v),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GrowError {
+    /// New capacity is not larger than current one, or too large for the buffer.
+    InvalidCapacity,
+    Closed,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+}
 
 pub fn queue<S, R, T>(capacity: usize) -> (Sender<S, R, T>, Receiver<S, R, T>) where
     S: Sequence, R: Sequence
 {
-    let head = Head::new(S::default(), R::default());
+    from_buffer(Buffer::new(Head::new(S::default(), R::default()), capacity))
+}
+
+/// Create a channel with a single receiver, whose senders can `force_send`.
+///
+/// Receiver can't be cloned, but its sequence is claimed atomically
+/// so senders can evict messages racing with it.
+///
+/// ```compile_fail
+/// use ringbuf::queue::bounded;
+/// use ringbuf::sequence::owned::Owned;
+///
+/// let (_tx, rx) = bounded::overwriting_queue::<Owned, u32>(2);
+/// let _ = rx.clone();
+/// ```
+pub fn overwriting_queue<S, T>(capacity: usize) -> (Sender<S, Evictable, T>, Receiver<S, Evictable, T>) where
+    S: Sequence
+{
+    queue(capacity)
+}
 
-    let sender = SenderHead::new(head.clone(), capacity);
-    let receiver = ReceiverHead::new(head.clone());
-    let buffer = Buffer::new(head, capacity);
+/// Create a channel over given buffer which is not used yet.
+pub(crate) fn from_buffer<S, R, T>(
+    buffer: Buffer<Arc<Head<S, R>>, T>
+) -> (Sender<S, R, T>, Receiver<S, R, T>) where
+    S: Sequence, R: Sequence
+{
+    let sender = SenderHead::new(buffer.clone());
+    let receiver = ReceiverHead::new(buffer.head().clone());
 
     // unwrap() is ok for newly created half
     let sender_half = Half::new(buffer.clone(), sender).unwrap();
-    let receiver_half = Half::new(buffer, receiver).unwrap();
+    let receiver_half = Half::new(buffer.clone(), receiver).unwrap();
 
     let sender = Sender {
         half: Some(sender_half),
+        buf: buffer.clone(),
+        evict_cache: None,
+        auto_grow: None,
+        #[cfg(feature = "futures")]
+        pending: None,
     };
     let receiver = Receiver {
         half: Some(receiver_half),
+        buf: buffer,
     };
 
     (sender, receiver)
@@ -61,56 +158,568 @@ pub fn queue<S, R, T>(capacity: usize) -> (Sender<S, R, T>, Receiver<S, R, T>) w
 
 impl<S: Sequence, R: Sequence, T> Sender<S, R, T> {
     pub fn is_closed(&self) -> bool {
-        self.half.as_ref().map_or(true, |half| half.is_closed())
+        self.half.as_ref().is_none_or(Half::is_closed)
+    }
+
+    /// Number of messages in the buffer.
+    ///
+    /// Other halves may advance concurrently, so it's only a snapshot
+    /// which can be outdated right after it's returned. It never exceeds the capacity.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether the buffer is empty. Same as `len() == 0`, with the same consistency.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Whether the buffer is full. Same as `len() == capacity()`, with the same consistency.
+    pub fn is_full(&self) -> bool {
+        self.buf.is_full()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Number of senders alive. It may be outdated as they can be cloned or dropped concurrently.
+    pub fn sender_count(&self) -> usize {
+        self.buf.sender_count()
+    }
+
+    /// Number of receivers alive. It may be outdated as they can be cloned or dropped concurrently.
+    pub fn receiver_count(&self) -> usize {
+        self.buf.receiver_count()
+    }
+
+    /// Whether every receivers are dropped or closed the channel.
+    ///
+    /// Messages sent after that are never received.
+    pub fn is_disconnected(&self) -> bool {
+        self.half.as_ref().is_some_and(Half::is_disconnected)
     }
 
     pub fn close(&mut self) {
         self.half.as_mut().map_or((), Half::close)
     }
 
+    pub(crate) fn waiters(&self) -> &WaitList<Waker> {
+        self.buf.head().waiters()
+    }
+
     pub fn try_send(&mut self, msg: T) -> Result<(), SendError<T>> {
+        let res = match &mut self.half {
+            Some(half) => half.try_advance(msg),
+            None => return Err(SendError::Closed(msg)),
+        };
+
+        match res {
+            Ok(()) => {
+                self.sent();
+                Ok(())
+            }
+            Err(AdvanceError::BufferFull(msg)) if self.grow_if_full() => self.try_send(msg),
+            Err(e) => Err(SendError::from(e)),
+        }
+    }
+
+    /// Send messages as many as the buffer can hold.
+    ///
+    /// Slots are claimed and committed in chunks as large as the lower bound of
+    /// the iterator's size hint, which is much cheaper than sending them one by one.
+    /// Returns the number of sent messages and the iterator of unsent ones.
+    ///
+    /// If the iterator yields less than it claimed, or panics, slots left unused
+    /// are given back. If other senders claimed after them, they're left
+    /// as holes which receivers step over, and the channel stays open.
+    ///
+    /// If the channel is closed while sending, messages written to the buffer are dropped.
+    pub fn try_send_many<I: IntoIterator<Item=T>>(&mut self, msgs: I) -> (usize, Peekable<I::IntoIter>) {
+        let mut msgs = msgs.into_iter().peekable();
+
+        let sent = match &mut self.half {
+            Some(half) => half.try_advance_many(&mut msgs, |()| {}),
+            None => 0,
+        };
+
+        (sent, msgs)
+    }
+
+    /// Send clones of messages in the slice as many as the buffer can hold at once.
+    ///
+    /// Returns the number of sent messages.
+    pub fn try_send_slice(&mut self, msgs: &[T]) -> usize where T: Clone {
+        self.try_send_many(msgs.iter().cloned()).0
+    }
+
+    /// Send a message, blocking current thread while the buffer is full.
+    ///
+    /// This method only fails if the channel is closed, with `SendError::Disconnected`
+    /// if it's closed by receivers.
+    pub fn send_blocking(&mut self, msg: T) -> Result<(), SendError<T>> {
         if let Some(half) = &mut self.half {
-            half.try_advance(msg).map_err(SendError::from)
+            half.advance(msg, None).map_err(SendError::from)
         } else {
             Err(SendError::Closed(msg))
         }
     }
+
+    /// Send a message asynchronously.
+    ///
+    /// Returned future resolves when the message is sent, or fails with
+    /// `SendError::Closed` or `SendError::Disconnected` if the channel is closed.
+    /// Dropping it before completion drops the message, and `SendFuture::cancel`
+    /// can be used to take it back instead.
+    pub fn send(&mut self, msg: T) -> SendFuture<'_, S, R, T> {
+        SendFuture::new(self, msg)
+    }
+
+    /// Send a message, blocking current thread for at most `timeout`
+    /// while the buffer is full.
+    pub fn send_timeout(&mut self, msg: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        // Too far deadline is same as no deadline
+        self.send_until(msg, Instant::now().checked_add(timeout))
+    }
+
+    /// Send a message, blocking current thread until `deadline`
+    /// while the buffer is full.
+    pub fn send_deadline(&mut self, msg: T, deadline: Instant) -> Result<(), SendTimeoutError<T>> {
+        self.send_until(msg, Some(deadline))
+    }
+
+    fn send_until(
+        &mut self, mut msg: T, deadline: Option<Instant>
+    ) -> Result<(), SendTimeoutError<T>> {
+        if self.auto_grow.is_none() {
+            return match &mut self.half {
+                Some(half) => half.advance(msg, deadline).map_err(SendTimeoutError::from),
+                None => Err(SendTimeoutError::Closed(msg)),
+            };
+        }
+
+        loop {
+            msg = match self.try_send(msg) {
+                Ok(()) => return Ok(()),
+                Err(SendError::BufferFull(msg)) => msg,
+                Err(SendError::Closed(msg)) => return Err(SendTimeoutError::Closed(msg)),
+                Err(SendError::Disconnected(msg)) => return Err(SendTimeoutError::Disconnected(msg)),
+            };
+
+            // Wake up to grow the buffer, as `try_send` above started to count the period
+            let grow_at = self.auto_grow.as_ref()
+                .and_then(|grow| grow.full_since.map(|since| since + grow.period));
+            let until = match (deadline, grow_at) {
+                (Some(deadline), Some(grow_at)) => Some(cmp::min(deadline, grow_at)),
+                (deadline, grow_at) => deadline.or(grow_at),
+            };
+
+            msg = match &mut self.half {
+                Some(half) => match half.advance(msg, until) {
+                    Ok(()) => {
+                        self.sent();
+                        return Ok(());
+                    }
+                    Err(AdvanceError::BufferFull(msg)) => msg,
+                    Err(e) => return Err(SendTimeoutError::from(e)),
+                },
+                None => return Err(SendTimeoutError::Closed(msg)),
+            };
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(SendTimeoutError::Timeout(msg));
+            }
+        }
+    }
+
+    /// Reset the period of auto grow, as the buffer is not full.
+    fn sent(&mut self) {
+        if let Some(grow) = &mut self.auto_grow {
+            grow.full_since = None;
+        }
+    }
+
+    /// Grow the buffer if it's been full for the period set by `grow_when_full`.
+    ///
+    /// Called when a send finds the buffer full. Returns whether it's grown.
+    fn grow_if_full(&mut self) -> bool {
+        let (half, grow) = match (&self.half, &mut self.auto_grow) {
+            (Some(half), Some(grow)) => (half, grow),
+            _ => return false,
+        };
+
+        let now = Instant::now();
+        let since = *grow.full_since.get_or_insert(now);
+        let capacity = cmp::min(self.buf.capacity().saturating_mul(2), grow.max_capacity);
+
+        if now < since + grow.period || capacity <= self.buf.capacity() ||
+            !buffer::is_valid_capacity(capacity) || half.is_closed()
+        {
+            return false;
+        }
+
+        // Every claimed slots are settled, as slots and batches borrow this sender
+        unsafe {
+            (grow.grow)(half.head(), capacity);
+        }
+        grow.full_since = None;
+        true
+    }
+
+    /// Claim a slot of the buffer to construct a message in place.
+    ///
+    /// The message is sent when returned slot is committed.
+    ///
+    /// Slots of shared senders are committed in order, so other senders
+    /// can't commit their messages while this slot is held.
+    /// If it's dropped after they claimed later slots, it's committed
+    /// as an abandoned slot which receivers step over.
+    pub fn try_reserve(&mut self) -> Result<SendSlot<'_, S, R, T>, SendError<()>> {
+        match &mut self.half {
+            Some(half) => half.try_claim_many(1).map(SendSlot::new).map_err(SendError::from),
+            None => Err(SendError::Closed(())),
+        }
+    }
+}
+
+impl<R: Sequence, T> Sender<Owned, R, T> {
+    /// Grow the buffer to given capacity, while receivers keep receiving.
+    ///
+    /// Messages already in the buffer are not moved but received from the current storage,
+    /// and new messages are stored in the new one, so the order is kept.
+    /// The current storage is freed on next grow after they're received, or with the channel.
+    ///
+    /// This is only available for owned sender, as other senders may be writing
+    /// to the current storage concurrently. Channels with shared senders,
+    /// like mpsc or mpmc ones, can't grow.
+    ///
+    /// The channel never grows by itself unless `grow_when_full` is set.
+    /// To grow the buffer on other conditions, try it after `send_timeout` as below.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ringbuf::queue::bounded::{self, SendTimeoutError};
+    /// use ringbuf::sequence::owned::Owned;
+    ///
+    /// let (mut tx, mut rx) = bounded::queue::<Owned, Owned, u32>(2);
+    /// tx.try_send(1).unwrap();
+    /// tx.try_send(2).unwrap();
+    ///
+    /// if let Err(SendTimeoutError::Timeout(msg)) = tx.send_timeout(3, Duration::from_millis(10)) {
+    ///     let capacity = tx.capacity() * 2;
+    ///     tx.try_grow(capacity).unwrap();
+    ///     tx.try_send(msg).unwrap();
+    /// }
+    ///
+    /// assert_eq!(tx.capacity(), 4);
+    /// assert_eq!(rx.try_recv(), Ok(Some(1)));
+    /// assert_eq!(rx.try_recv(), Ok(Some(2)));
+    /// assert_eq!(rx.try_recv(), Ok(Some(3)));
+    /// ```
+    pub fn try_grow(&mut self, capacity: usize) -> Result<(), GrowError> {
+        let half = match &self.half {
+            Some(half) if !half.is_closed() => half,
+            _ => return Err(GrowError::Closed),
+        };
+
+        if capacity <= self.buf.capacity() || !buffer::is_valid_capacity(capacity) {
+            return Err(GrowError::InvalidCapacity);
+        }
+
+        // Every claimed slots are settled, as slots and batches borrow this sender
+        unsafe {
+            half.head().grow(capacity);
+        }
+        Ok(())
+    }
+
+    /// Grow the buffer twice, up to `max_capacity`, when it's been full for `period`.
+    ///
+    /// The period starts when `try_send`, `send_blocking`, `send_timeout`
+    /// or `send_deadline` finds the buffer full, and it's reset by successful send
+    /// of them. Blocking sends wake up to grow the buffer when the period is over.
+    /// Other methods like `try_send_many` or futures never grow the buffer.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ringbuf::queue::bounded;
+    /// use ringbuf::sequence::owned::Owned;
+    ///
+    /// let (mut tx, mut rx) = bounded::queue::<Owned, Owned, u32>(2);
+    /// tx.grow_when_full(Duration::from_millis(10), 4);
+    ///
+    /// for i in 0..4 {
+    ///     tx.send_timeout(i, Duration::from_secs(10)).unwrap();
+    /// }
+    /// assert_eq!(tx.capacity(), 4);
+    /// assert_eq!(rx.try_recv(), Ok(Some(0)));
+    /// ```
+    pub fn grow_when_full(&mut self, period: Duration, max_capacity: usize) {
+        self.auto_grow = Some(AutoGrow {
+            period,
+            max_capacity,
+            full_since: None,
+            grow: SenderHead::grow,
+        });
+    }
+}
+
+impl<S: Sequence, R: Evict, T> Sender<S, R, T> {
+    /// Send a message, evicting the oldest one if the buffer is full.
+    ///
+    /// Returns the evicted message if any. If other senders evict concurrently,
+    /// more than one message can be evicted and the older ones are dropped.
+    /// As the sender acts like a receiver to evict, receivers should be shared,
+    /// or the channel should be created by `overwriting_queue` for a single receiver.
+    pub fn force_send(&mut self, mut msg: T) -> Result<Option<T>, SendError<T>> {
+        let Sender { half, evict_cache, .. } = self;
+        let half = match half {
+            Some(half) => half,
+            None => return Err(SendError::Closed(msg)),
+        };
+        let mut evicted = None;
+
+        loop {
+            match half.try_advance(msg) {
+                Ok(()) => return Ok(evicted),
+                Err(AdvanceError::BufferFull(v)) => msg = v,
+                Err(e) => return Err(SendError::from(e)),
+            }
+
+            let cache = match evict_cache {
+                Some(cache) => cache,
+                None => match half.head().evict_cache() {
+                    Ok(cache) => evict_cache.get_or_insert(cache),
+                    Err(_) => return Err(SendError::Closed(msg)),
+                },
+            };
+
+            if let Some(old) = half.head().evict(half.buf(), cache) {
+                evicted = Some(old);
+            }
+        }
+    }
 }
 
 impl<S: MultiCache, R: Sequence, T> Clone for Sender<S, R, T> {
     fn clone(&self) -> Self {
         Sender {
             half: self.half.as_ref().and_then(Half::try_clone),
+            buf: self.buf.clone(),
+            evict_cache: None,
+            auto_grow: None,
+            #[cfg(feature = "futures")]
+            pending: None,
         }
     }
 }
 
 impl<S: Sequence, R: Sequence, T> Receiver<S, R, T> {
     pub fn is_closed(&self) -> bool {
-        self.half.as_ref().map_or(true, Half::is_closed)
+        self.half.as_ref().is_none_or(Half::is_closed)
+    }
+
+    /// Number of messages in the buffer.
+    ///
+    /// Other halves may advance concurrently, so it's only a snapshot
+    /// which can be outdated right after it's returned. It never exceeds the capacity.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether the buffer is empty. Same as `len() == 0`, with the same consistency.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Whether the buffer is full. Same as `len() == capacity()`, with the same consistency.
+    pub fn is_full(&self) -> bool {
+        self.buf.is_full()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Number of senders alive. It may be outdated as they can be cloned or dropped concurrently.
+    pub fn sender_count(&self) -> usize {
+        self.buf.sender_count()
+    }
+
+    /// Number of receivers alive. It may be outdated as they can be cloned or dropped concurrently.
+    pub fn receiver_count(&self) -> usize {
+        self.buf.receiver_count()
+    }
+
+    /// Whether every senders are dropped or closed the channel.
+    ///
+    /// Messages sent before that can still be received.
+    pub fn is_disconnected(&self) -> bool {
+        self.half.as_ref().is_some_and(Half::is_disconnected)
     }
 
     pub fn close(&mut self) {
         self.half.as_mut().map_or((), Half::close)
     }
 
+    pub(crate) fn waiters(&self) -> &WaitList<Waker> {
+        self.buf.head().waiters()
+    }
+
+    /// Receive a message if available.
+    ///
+    /// Messages sent before the channel is closed are still received, and it returns
+    /// `Ok(None)` only after every messages are received.
+    /// If this receiver closed the channel by itself, remaining messages are left to others.
     pub fn try_recv(&mut self) -> Result<Option<T>, RecvError> {
         if let Some(half) = &mut self.half {
             match half.try_advance(()) {
                 Ok(msg) => Ok(Some(msg)),
                 Err(AdvanceError::BufferFull(())) => Err(RecvError),
-                Err(AdvanceError::Closed(())) => Ok(None),
+                Err(_) => Ok(None),
             }
         } else {
             Ok(None)
         }
     }
+
+    /// Receive up to `max` messages at once and append them to `buf`.
+    ///
+    /// Slots for them are claimed and committed at once, which is much cheaper
+    /// than receiving them one by one. Returns the number of received messages.
+    pub fn try_recv_many<E: Extend<T>>(&mut self, buf: &mut E, max: usize) -> usize {
+        let half = match &mut self.half {
+            Some(half) if max != 0 => half,
+            _ => return 0,
+        };
+
+        match half.try_claim_many(max) {
+            Ok(mut batch) => {
+                let len = batch.remaining();
+                buf.extend((0..len).map(|_| batch.advance(())));
+                // Receiver sequence is never closed, so commit never fails.
+                // Committed slots may include abandoned ones, so don't count them
+                let _ = batch.finish();
+                len
+            }
+            Err(_) => 0,
+        }
+    }
+
+    /// Take every messages currently in the buffer at once.
+    ///
+    /// Slots for them are claimed at once, and each slot is committed as its message
+    /// is taken from returned iterator. Messages not taken are dropped with it.
+    /// If the iterator is leaked, they're left in the buffer instead,
+    /// and shared receivers can't receive past them anymore.
+    pub fn drain(&mut self) -> Drain<'_, S, R, T> {
+        Drain::new(self.half.as_mut().and_then(|half| half.try_claim_many(!0).ok()))
+    }
+
+    /// Receive a message, blocking current thread while the buffer is empty.
+    ///
+    /// Returns `None` if the channel is closed and every messages are received.
+    pub fn recv_blocking(&mut self) -> Option<T> {
+        if let Some(half) = &mut self.half {
+            half.advance((), None).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Receive a message asynchronously.
+    ///
+    /// Returned future resolves to `None` if the channel is closed
+    /// and every messages are received.
+    pub fn recv(&mut self) -> RecvFuture<'_, S, R, T> {
+        RecvFuture::new(self)
+    }
+
+    /// Receive a message, blocking current thread for at most `timeout`
+    /// while the buffer is empty.
+    ///
+    /// Returns `Ok(None)` if the channel is closed and every messages are received.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<T>, RecvTimeoutError> {
+        // Too far deadline is same as no deadline
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Receive a message, blocking current thread until `deadline`
+    /// while the buffer is empty.
+    ///
+    /// Returns `Ok(None)` if the channel is closed and every messages are received.
+    pub fn recv_deadline(&mut self, deadline: Instant) -> Result<Option<T>, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Result<Option<T>, RecvTimeoutError> {
+        if let Some(half) = &mut self.half {
+            match half.advance((), deadline) {
+                Ok(msg) => Ok(Some(msg)),
+                Err(AdvanceError::BufferFull(())) => Err(RecvTimeoutError::Timeout),
+                Err(_) => Ok(None),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<S: Sequence, T> Receiver<S, Owned, T> {
+    /// Borrow a message in place without moving it out of the buffer.
+    ///
+    /// The message is dropped and its slot is given to senders
+    /// when returned guard is dropped.
+    ///
+    /// This is only available for owned receiver. Slots are committed in order,
+    /// so a guard held by a shared receiver would block every other receivers.
+    ///
+    /// ```compile_fail
+    /// use ringbuf::queue::bounded;
+    /// use ringbuf::sequence::shared::Shared;
+    ///
+    /// let (_tx, mut rx) = bounded::queue::<Shared, Shared, u32>(2);
+    /// rx.try_recv_ref();
+    /// ```
+    pub fn try_recv_ref(&mut self) -> Result<Option<RecvGuard<'_, S, Owned, T>>, RecvError> {
+        if let Some(half) = &mut self.half {
+            match half.try_claim_many(1) {
+                Ok(batch) => Ok(Some(RecvGuard::new(batch))),
+                Err(AdvanceError::BufferFull(())) => Err(RecvError),
+                Err(_) => Ok(None),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Borrow the next message without receiving it.
+    ///
+    /// This is only available for owned receiver, as shared ones
+    /// may take the message while it's borrowed.
+    pub fn try_peek_ref(&self) -> Result<Option<&T>, RecvError> {
+        if let Some(half) = &self.half {
+            match half.peek() {
+                Ok(msg) => Ok(Some(unsafe { &*msg })),
+                Err(AdvanceError::BufferFull(())) => Err(RecvError),
+                Err(_) => Ok(None),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Clone the next message without receiving it.
+    pub fn try_peek(&self) -> Result<Option<T>, RecvError> where T: Clone {
+        self.try_peek_ref().map(|msg| msg.cloned())
+    }
 }
 
 impl<S: Sequence, R: MultiCache, T> Clone for Receiver<S, R, T> {
     fn clone(&self) -> Self {
         Receiver {
-            half: self.half.as_ref().and_then(Half::try_clone)
+            half: self.half.as_ref().and_then(Half::try_clone),
+            buf: self.buf.clone(),
         }
     }
 }
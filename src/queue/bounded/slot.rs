@@ -0,0 +1,123 @@
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut, Drop};
+use std::ptr;
+use std::fmt;
+
+use sequence::Sequence;
+use role::Borrow;
+
+use super::SendError;
+use super::head::{SenderBatch, ReceiverBatch};
+
+/// Claimed slot of the buffer returned by `Sender::try_reserve`.
+///
+/// Message can be constructed in place, and is sent when the slot is committed.
+/// Dropping an uncommitted slot gives it back to the channel, and the message
+/// written into it is leaked. If other senders claimed later slots, it's
+/// committed as an abandoned slot instead, which is never received.
+///
+/// Leaked slot of an owned sender is claimed again by the next send.
+/// Leaked slot of a shared sender is never committed, so other senders
+/// can't commit their messages anymore.
+pub struct SendSlot<'a, S: Sequence + 'a, R: Sequence + 'a, T: 'a> {
+    batch: SenderBatch<'a, S, R, T>,
+}
+
+impl<'a, S: Sequence, R: Sequence, T> SendSlot<'a, S, R, T> {
+    pub(super) fn new(batch: SenderBatch<'a, S, R, T>) -> Self {
+        debug_assert_eq!(batch.remaining(), 1);
+
+        SendSlot {
+            batch,
+        }
+    }
+
+    /// Uninitialized memory of this slot.
+    pub fn get_mut(&mut self) -> &mut MaybeUninit<T> {
+        unsafe {
+            &mut *(self.batch.next_slot() as *mut MaybeUninit<T>)
+        }
+    }
+
+    /// Write message into this slot and send it.
+    pub fn write(mut self, msg: T) -> Result<(), SendError<T>> {
+        self.get_mut().write(msg);
+
+        unsafe {
+            self.commit()
+        }
+    }
+
+    /// Send the message constructed in this slot.
+    ///
+    /// If the channel is closed, the message is moved out and returned back.
+    ///
+    /// # Safety
+    ///
+    /// The slot should be initialized via `get_mut`.
+    pub unsafe fn commit(mut self) -> Result<(), SendError<T>> {
+        self.batch.skip();
+
+        let mut msg = None;
+        match self.batch.finish_with(|slot| msg = Some(ptr::read(slot))) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(SendError::from(e.map(|()| msg.expect("Closed before commit")))),
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> fmt::Debug for SendSlot<'a, S, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SendSlot").finish()
+    }
+}
+
+/// Message borrowed in place from the buffer, returned by `Receiver::try_recv_ref`.
+///
+/// The message is dropped and its slot is committed when the guard is dropped.
+pub struct RecvGuard<'a, S: Sequence + 'a, R: Sequence + 'a, T: 'a> {
+    msg: *mut T,
+    // Should be dropped after the message
+    _batch: ReceiverBatch<'a, S, R, T>,
+}
+
+impl<'a, S: Sequence, R: Sequence, T> RecvGuard<'a, S, R, T> {
+    pub(super) fn new(mut batch: ReceiverBatch<'a, S, R, T>) -> Self {
+        debug_assert_eq!(batch.remaining(), 1);
+
+        RecvGuard {
+            msg: batch.advance_as::<Borrow<T>>(()),
+            _batch: batch,
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> Deref for RecvGuard<'a, S, R, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.msg }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> DerefMut for RecvGuard<'a, S, R, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.msg }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T> Drop for RecvGuard<'a, S, R, T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.msg);
+        }
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence, T: fmt::Debug> fmt::Debug for RecvGuard<'a, S, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RecvGuard")
+            .field(&**self)
+            .finish()
+    }
+}
@@ -0,0 +1,91 @@
+//! `Stream` and `Sink` implementations, enabled by `futures` feature.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::{Stream, Sink};
+
+use sequence::Sequence;
+
+use super::{Sender, Receiver, SendError};
+use super::half::AdvanceError;
+
+impl<S: Sequence, R: Sequence, T> Stream for Receiver<S, R, T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        let half = match &mut self.get_mut().half {
+            Some(half) => half,
+            None => return Poll::Ready(None),
+        };
+
+        match half.poll_advance((), cx.waker()) {
+            Ok(msg) => Poll::Ready(Some(msg)),
+            Err(AdvanceError::BufferFull(())) => Poll::Pending,
+            Err(_) => Poll::Ready(None),
+        }
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Sink<T> for Sender<S, R, T> {
+    type Error = SendError<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SendError<T>>> {
+        self.poll_flush(cx)
+    }
+
+    /// Accept a message to be sent on next flush.
+    ///
+    /// If the previous message is still pending as `poll_ready` is not called
+    /// or not ready yet, the new one is returned with `SendError::BufferFull`.
+    fn start_send(self: Pin<&mut Self>, msg: T) -> Result<(), SendError<T>> {
+        let this = self.get_mut();
+
+        if this.half.is_none() {
+            return Err(SendError::Closed(msg));
+        }
+
+        if this.pending.is_some() {
+            return Err(SendError::BufferFull(msg));
+        }
+
+        this.pending = Some(msg);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SendError<T>>> {
+        let this = self.get_mut();
+
+        let msg = match this.pending.take() {
+            Some(msg) => msg,
+            None => return Poll::Ready(Ok(())),
+        };
+
+        let half = match &mut this.half {
+            Some(half) => half,
+            None => return Poll::Ready(Err(SendError::Closed(msg))),
+        };
+
+        match half.poll_advance(msg, cx.waker()) {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(AdvanceError::BufferFull(msg)) => {
+                this.pending = Some(msg);
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(SendError::from(e))),
+        }
+    }
+
+    /// Flush pending message and drop this sender.
+    ///
+    /// The channel is closed if this was the last sender.
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SendError<T>>> {
+        match self.as_mut().poll_flush(cx) {
+            Poll::Ready(Ok(())) => {
+                self.get_mut().half = None;
+                Poll::Ready(Ok(()))
+            }
+            res => res,
+        }
+    }
+}
@@ -0,0 +1,307 @@
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::marker::PhantomData;
+use std::task::Waker;
+use std::ptr;
+use std::fmt;
+
+use role::{self, Kind};
+use counter::{Counter, CounterRange, AtomicCounter};
+use sequence::{Sequence, Limit, Offset};
+use buffer::{Buffer, BufRange};
+use queue::waiters::WaitList;
+use queue::lock::{SpinLock, Guard};
+use queue::bounded::half::{Half, HeadHalf};
+
+use super::Policy;
+
+pub(crate) struct Head<S: Sequence> {
+    sender: S,
+    /// Counter of the oldest slot not released yet. This is never closed.
+    tail: AtomicCounter,
+    policy: Policy,
+    subscribers: SpinLock<Vec<Arc<Cursor>>>,
+    sender_count: AtomicUsize,
+    subscriber_count: AtomicUsize,
+    waiters: WaitList<Waker>,
+}
+
+/// Receiver cursor of each subscriber.
+///
+/// Releasing slots locks every cursors, so it's locked while the message is cloned.
+pub(crate) struct Cursor {
+    state: SpinLock<CursorState>,
+}
+
+#[derive(Debug)]
+pub(crate) struct CursorState {
+    pub count: Counter,
+    /// Number of messages skipped by `Policy::Lag` and not reported yet.
+    pub lagged: usize,
+    /// Set by `Policy::Disconnect`, once the subscriber fell behind.
+    pub disconnected: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct SenderHead<S: Sequence, T> {
+    head: Arc<Head<S>>,
+    buf: Buffer<Arc<Head<S>>, T>,
+    role: PhantomData<role::Send<T>>,
+}
+
+pub(crate) type SenderHalf<S, T> = Half<Arc<Head<S>>, SenderHead<S, T>, T>;
+
+impl<S: Sequence> Head<S> {
+    pub fn new(sender: S, policy: Policy) -> Arc<Self> {
+        Arc::new(Head {
+            sender,
+            tail: AtomicCounter::default(),
+            policy,
+            subscribers: SpinLock::new(Vec::new()),
+            sender_count: 0.into(),
+            subscriber_count: 0.into(),
+            waiters: WaitList::new(),
+        })
+    }
+
+    pub fn sender(&self) -> &S {
+        &self.sender
+    }
+
+    pub fn waiters(&self) -> &WaitList<Waker> {
+        &self.waiters
+    }
+
+    /// Close the channel and wake up every blocked halves.
+    pub fn close(&self) {
+        self.sender.counter().close();
+        self.waiters.notify_all();
+    }
+
+    fn tail(&self) -> Counter {
+        match self.tail.fetch() {
+            Ok(count) => count,
+            Err(count) => count,
+        }
+    }
+
+    /// Register new cursor which starts from the next message to be sent,
+    /// or from the position of `from` if given.
+    pub fn subscribe(&self, from: Option<&Cursor>) -> Arc<Cursor> {
+        let mut subscribers = self.subscribers.lock();
+
+        // Slots can't be released while the registry is locked,
+        // so the cursor is never behind the tail.
+        let count = match from {
+            Some(cursor) => cursor.lock().count,
+            None => self.sender.fetch_last(),
+        };
+
+        self.subscriber_count.fetch_add(1, Ordering::Release);
+        let cursor = Arc::new(Cursor {
+            state: SpinLock::new(CursorState {
+                count,
+                lagged: 0,
+                disconnected: false,
+            }),
+        });
+        subscribers.push(cursor.clone());
+        cursor
+    }
+
+    /// Deregister given cursor and release slots only it was holding.
+    pub fn unsubscribe<T>(&self, cursor: &Arc<Cursor>, buf: &Buffer<Arc<Self>, T>) {
+        let mut subscribers = self.subscribers.lock();
+
+        // Disconnected one is already removed
+        if let Some(idx) = subscribers.iter().position(|c| Arc::ptr_eq(c, cursor)) {
+            subscribers.swap_remove(idx);
+            self.leave();
+        }
+
+        self.release(&subscribers, buf);
+    }
+
+    /// Close the channel if the last subscriber leaves.
+    fn leave(&self) {
+        if self.subscriber_count.fetch_sub(1, Ordering::Release) == 1 {
+            self.close();
+        }
+    }
+
+    /// Release slots every subscribers have passed, if given count was the tail.
+    pub fn try_release<T>(&self, prev: Counter, buf: &Buffer<Arc<Self>, T>) {
+        if prev == self.tail() {
+            let subscribers = self.subscribers.lock();
+            self.release(&subscribers, buf);
+        }
+    }
+
+    /// Force lagging subscribers to catch up `target`, to make room for new message.
+    fn evict<T>(&self, target: Counter, buf: &Buffer<Arc<Self>, T>) {
+        let mut subscribers = self.subscribers.lock();
+
+        match self.policy {
+            Policy::Block => {}
+            Policy::Lag => {
+                for cursor in subscribers.iter() {
+                    let mut state = cursor.lock();
+                    if state.count < target {
+                        state.lagged += (target - state.count) as usize;
+                        state.count = target;
+                    }
+                }
+            }
+            Policy::Disconnect => {
+                subscribers.retain(|cursor| {
+                    let mut state = cursor.lock();
+                    if state.count < target {
+                        state.disconnected = true;
+                        self.leave();
+                    }
+                    !state.disconnected
+                });
+            }
+        }
+
+        self.release(&subscribers, buf);
+    }
+
+    /// Drop messages every registered subscribers have passed.
+    fn release<T>(&self, subscribers: &[Arc<Cursor>], buf: &Buffer<Arc<Self>, T>) {
+        let tail = self.tail();
+        let mut new_tail = self.sender.fetch_last();
+
+        for cursor in subscribers {
+            let count = cursor.lock().count;
+            if count < new_tail {
+                new_tail = count;
+            }
+        }
+
+        if new_tail <= tail {
+            return;
+        }
+
+        for count in Counter::range(tail, new_tail) {
+            unsafe {
+                ptr::drop_in_place(buf.get(count));
+            }
+        }
+
+        let released = (new_tail - tail) as usize;
+        self.tail.add(released);
+
+        for _ in 0..released {
+            if !self.waiters.notify(Kind::Send) {
+                break;
+            }
+        }
+    }
+}
+
+impl<S: Sequence> BufRange for Arc<Head<S>> {
+    fn range(&self) -> CounterRange {
+        Counter::range(self.tail(), self.sender.fetch_last())
+    }
+}
+
+impl<S: Sequence + fmt::Debug> fmt::Debug for Head<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Head")
+            .field("sender", &self.sender)
+            .field("tail", &self.tail)
+            .field("policy", &self.policy)
+            .field("subscribers", &self.subscriber_count)
+            .field("waiters", &self.waiters)
+            .finish()
+    }
+}
+
+impl Cursor {
+    pub fn lock(&self) -> Guard<'_, CursorState> {
+        self.state.lock()
+    }
+}
+
+impl fmt::Debug for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Cursor")
+            .field(&*self.lock())
+            .finish()
+    }
+}
+
+impl<S: Sequence, T> SenderHead<S, T> {
+    pub fn new(head: Arc<Head<S>>, buf: Buffer<Arc<Head<S>>, T>) -> Self {
+        SenderHead {
+            head,
+            buf,
+            role: PhantomData,
+        }
+    }
+}
+
+impl<S: Sequence, T> HeadHalf for SenderHead<S, T> {
+    type Seq = S;
+    type Role = role::Send<T>;
+
+    fn seq(&self) -> &S {
+        &self.head.sender
+    }
+
+    fn amount(&self) -> &AtomicUsize {
+        &self.head.sender_count
+    }
+
+    fn close_counter(&self) -> &AtomicCounter {
+        self.head.sender.counter()
+    }
+
+    fn close(&self) {
+        self.head.close()
+    }
+
+    fn waiters(&self) -> &WaitList<Waker> {
+        &self.head.waiters
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.head.subscriber_count.load(Ordering::Acquire) == 0
+    }
+
+    fn notify_counterpart(&self) -> bool {
+        // Every subscribers receive every messages
+        self.head.waiters.notify_every(Kind::Receive)
+    }
+}
+
+impl<S: Sequence, T> Limit for SenderHead<S, T> {
+    fn count(&self) -> Counter {
+        let capacity = self.buf.capacity();
+        let limit = Offset(&self.head.tail, capacity);
+
+        if self.head.policy == Policy::Block {
+            return limit.count();
+        }
+
+        // Make room for the next message by forcing slow subscribers
+        let sender_last = self.head.sender.fetch_last();
+        if limit.count() <= sender_last {
+            self.head.evict(sender_last + 1 - capacity, &self.buf);
+        }
+
+        limit.count()
+    }
+}
+
+impl<S: Sequence, T> Clone for SenderHead<S, T> {
+    fn clone(&self) -> Self {
+        SenderHead {
+            head: Arc::clone(&self.head),
+            buf: self.buf.clone(),
+            role: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Broadcast channel where every subscriber receives every message.
+//!
+//! Each subscriber has its own cursor over the shared ring buffer, and messages
+//! are dropped once the slowest subscriber passes them. How senders treat slow
+//! subscribers when the buffer is full is configured by `Policy`.
+
+use std::sync::Arc;
+use std::ops::Drop;
+use std::fmt;
+
+use sequence::{Sequence, MultiCache};
+use buffer::Buffer;
+use role::Kind;
+use queue::bounded::SendError;
+use queue::bounded::half::Half;
+use queue::park::Parker;
+
+mod head;
+
+use self::head::{Head, Cursor, SenderHead, SenderHalf};
+
+/// What senders do when the buffer is full due to slow subscribers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Wait until the slowest subscriber receives the oldest message.
+    Block,
+    /// Skip the oldest message for slow subscribers,
+    /// which get `RecvError::Lagged` on their next receive.
+    Lag,
+    /// Disconnect slow subscribers,
+    /// which get `RecvError::Disconnected` on every receive after that.
+    Disconnect,
+}
+
+#[derive(Debug)]
+pub struct Sender<S: Sequence, T> {
+    half: Option<SenderHalf<S, T>>,
+    buf: Buffer<Arc<Head<S>>, T>,
+}
+
+pub struct Subscriber<S: Sequence, T> {
+    head: Arc<Head<S>>,
+    buf: Buffer<Arc<Head<S>>, T>,
+    cursor: Arc<Cursor>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecvError {
+    /// No message is available for now.
+    Empty,
+    /// This subscriber missed given number of messages due to `Policy::Lag`.
+    /// Next receive continues from the oldest message in the buffer.
+    Lagged(usize),
+    /// This subscriber is disconnected due to `Policy::Disconnect`.
+    Disconnected,
+}
+
+/// Create a broadcast channel with given capacity and slow subscriber policy.
+///
+/// Subscriber created with the channel receives every messages.
+/// Other subscribers receive messages sent after they're created.
+///
+/// Messages should be `Sync` as subscribers clone them concurrently.
+pub fn channel<S, T>(capacity: usize, policy: Policy) -> (Sender<S, T>, Subscriber<S, T>) where
+    S: Sequence, T: Clone + Sync
+{
+    let head = Head::new(S::default(), policy);
+    let buffer = Buffer::new(head.clone(), capacity);
+
+    let sender = SenderHead::new(head.clone(), buffer.clone());
+
+    // unwrap() is ok for newly created half
+    let sender_half = Half::new(buffer.clone(), sender).unwrap();
+
+    let sender = Sender {
+        half: Some(sender_half),
+        buf: buffer.clone(),
+    };
+    let subscriber = Subscriber::new(head, buffer, None);
+
+    (sender, subscriber)
+}
+
+impl<S: Sequence, T: Clone> Sender<S, T> {
+    pub fn is_closed(&self) -> bool {
+        self.half.as_ref().is_none_or(Half::is_closed)
+    }
+
+    /// Whether every subscribers are dropped or disconnected.
+    pub fn is_disconnected(&self) -> bool {
+        self.half.as_ref().is_some_and(Half::is_disconnected)
+    }
+
+    pub fn close(&mut self) {
+        self.half.as_mut().map_or((), Half::close)
+    }
+
+    /// Send a message to every subscribers.
+    ///
+    /// With `Policy::Block`, this fails if the slowest subscriber hasn't received
+    /// the oldest message yet. Other policies make room for it instead,
+    /// but this still can fail while other senders are in the middle of sending.
+    pub fn try_send(&mut self, msg: T) -> Result<(), SendError<T>> {
+        if let Some(half) = &mut self.half {
+            half.try_advance(msg).map_err(SendError::from)
+        } else {
+            Err(SendError::Closed(msg))
+        }
+    }
+
+    /// Send a message, blocking current thread while the buffer is full.
+    pub fn send_blocking(&mut self, msg: T) -> Result<(), SendError<T>> {
+        if let Some(half) = &mut self.half {
+            half.advance(msg, None).map_err(SendError::from)
+        } else {
+            Err(SendError::Closed(msg))
+        }
+    }
+
+    /// Create new subscriber which receives messages sent after this call.
+    ///
+    /// Subscribers created after the channel is closed never receive messages.
+    pub fn subscribe(&self) -> Subscriber<S, T> {
+        Subscriber::new(self.buf.head().clone(), self.buf.clone(), None)
+    }
+}
+
+impl<S: MultiCache, T> Clone for Sender<S, T> {
+    fn clone(&self) -> Self {
+        Sender {
+            half: self.half.as_ref().and_then(Half::try_clone),
+            buf: self.buf.clone(),
+        }
+    }
+}
+
+impl<S: Sequence, T: Clone> Subscriber<S, T> {
+    fn new(head: Arc<Head<S>>, buf: Buffer<Arc<Head<S>>, T>, from: Option<&Cursor>) -> Self {
+        let cursor = head.subscribe(from);
+
+        Subscriber {
+            head,
+            buf,
+            cursor,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.head.sender().counter().fetch().is_err()
+    }
+
+    /// Receive a clone of the next message.
+    ///
+    /// Returns `Ok(None)` if the channel is closed and every messages are received.
+    pub fn try_recv(&mut self) -> Result<Option<T>, RecvError> {
+        let (msg, prev) = {
+            let mut state = self.cursor.lock();
+
+            if state.disconnected {
+                return Err(RecvError::Disconnected);
+            }
+
+            if state.lagged != 0 {
+                let lagged = state.lagged;
+                state.lagged = 0;
+                return Err(RecvError::Lagged(lagged));
+            }
+
+            let (last, closed) = match self.head.sender().counter().fetch() {
+                Ok(count) => (count, false),
+                Err(count) => (count, true),
+            };
+
+            if state.count == last {
+                return if closed {
+                    Ok(None)
+                } else {
+                    Err(RecvError::Empty)
+                };
+            }
+
+            let prev = state.count;
+            let msg = unsafe { (*self.buf.get(prev)).clone() };
+            state.count = prev + 1;
+            (msg, prev)
+        };
+
+        self.head.try_release(prev, &self.buf);
+        Ok(Some(msg))
+    }
+
+    /// Receive a clone of the next message, blocking current thread
+    /// while no message is available.
+    pub fn recv_blocking(&mut self) -> Result<Option<T>, RecvError> {
+        let parker = Parker::new();
+
+        loop {
+            match self.try_recv() {
+                Err(RecvError::Empty) => {}
+                res => return res,
+            }
+
+            let key = self.head.waiters().wait(Kind::Receive, parker.waker());
+
+            // Recheck as the sender may advanced before registration.
+            // Every subscribers are notified at once, so nothing to pass on cancel.
+            match self.try_recv() {
+                Err(RecvError::Empty) => {}
+                res => {
+                    self.head.waiters().cancel(key);
+                    return res;
+                }
+            }
+
+            parker.park();
+        }
+    }
+}
+
+impl<S: Sequence, T: Clone> Clone for Subscriber<S, T> {
+    /// Create new subscriber which starts from the same position as this one.
+    fn clone(&self) -> Self {
+        Subscriber::new(self.head.clone(), self.buf.clone(), Some(&self.cursor))
+    }
+}
+
+impl<S: Sequence, T> Drop for Subscriber<S, T> {
+    fn drop(&mut self) {
+        self.head.unsubscribe(&self.cursor, &self.buf);
+    }
+}
+
+impl<S: Sequence, T> fmt::Debug for Subscriber<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Subscriber")
+            .field("cursor", &self.cursor)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests;
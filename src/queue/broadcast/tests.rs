@@ -0,0 +1,144 @@
+use std::thread;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sequence::owned::Owned;
+use sequence::shared::Shared;
+use queue::bounded::SendError;
+use test_util::LoudDrop;
+
+use super::{channel, Policy, RecvError};
+
+#[test]
+fn test_every_subscriber() {
+    let (mut tx, mut rx1) = channel::<Owned, usize>(4, Policy::Block);
+    let mut rx2 = tx.subscribe();
+
+    tx.try_send(1).unwrap();
+    let mut rx3 = rx2.clone();
+    tx.try_send(2).unwrap();
+    let mut rx4 = tx.subscribe();
+
+    for rx in [&mut rx1, &mut rx2, &mut rx3].iter_mut() {
+        assert_eq!(rx.try_recv(), Ok(Some(1)));
+        assert_eq!(rx.try_recv(), Ok(Some(2)));
+        assert_eq!(rx.try_recv(), Err(RecvError::Empty));
+    }
+    assert_eq!(rx4.try_recv(), Err(RecvError::Empty));
+
+    tx.try_send(3).unwrap();
+    drop(tx);
+
+    for rx in [&mut rx1, &mut rx2, &mut rx3, &mut rx4].iter_mut() {
+        assert_eq!(rx.try_recv(), Ok(Some(3)));
+        assert_eq!(rx.try_recv(), Ok(None));
+    }
+}
+
+#[test]
+fn test_block_policy() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut fast) = channel::<Owned, LoudDrop>(2, Policy::Block);
+    let mut slow = fast.clone();
+
+    tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    assert!(fast.try_recv().unwrap().is_some());
+    assert!(fast.try_recv().unwrap().is_some());
+    assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+    // Slowest subscriber holds every messages
+    match tx.try_send(LoudDrop(drops.clone(), 0)) {
+        Err(SendError::BufferFull(_)) => {}
+        res => panic!("Unexpected result {:?}", res),
+    }
+    assert_eq!(drops.load(Ordering::Relaxed), 3);
+
+    assert!(slow.try_recv().unwrap().is_some());
+    assert_eq!(drops.load(Ordering::Relaxed), 5);
+    tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+
+    // Dropping the slowest one releases its messages
+    drop(slow);
+    assert_eq!(drops.load(Ordering::Relaxed), 6);
+
+    drop(tx);
+    drop(fast);
+    assert_eq!(drops.load(Ordering::Relaxed), 7);
+}
+
+#[test]
+fn test_lag_policy() {
+    let (mut tx, mut fast) = channel::<Owned, usize>(2, Policy::Lag);
+    let mut slow = fast.clone();
+
+    for i in 0..5 {
+        tx.try_send(i).unwrap();
+        assert_eq!(fast.try_recv(), Ok(Some(i)));
+    }
+
+    assert_eq!(slow.try_recv(), Err(RecvError::Lagged(3)));
+    assert_eq!(slow.try_recv(), Ok(Some(3)));
+    assert_eq!(slow.try_recv(), Ok(Some(4)));
+    assert_eq!(slow.try_recv(), Err(RecvError::Empty));
+}
+
+#[test]
+fn test_disconnect_policy() {
+    let (mut tx, mut fast) = channel::<Owned, usize>(2, Policy::Disconnect);
+    let mut slow = fast.clone();
+
+    for i in 0..5 {
+        tx.try_send(i).unwrap();
+        assert_eq!(fast.try_recv(), Ok(Some(i)));
+    }
+
+    assert_eq!(slow.try_recv(), Err(RecvError::Disconnected));
+    assert_eq!(slow.try_recv(), Err(RecvError::Disconnected));
+
+    // Disconnected subscriber doesn't hold the channel open
+    drop(fast);
+    assert!(tx.is_disconnected());
+    assert_eq!(tx.try_send(5), Err(SendError::Disconnected(5)));
+}
+
+#[test]
+fn test_blocking_broadcast() {
+    const COUNT: usize = 1000;
+
+    let (tx, rx) = channel::<Shared, usize>(4, Policy::Block);
+
+    let receivers: Vec<_> = (0..3)
+        .map(|_| {
+            let mut rx = rx.clone();
+            thread::spawn(move|| {
+                let mut acc = 0;
+                while let Some(num) = rx.recv_blocking().unwrap() {
+                    acc += num;
+                }
+                acc
+            })
+        })
+        .collect();
+    drop(rx);
+
+    let senders: Vec<_> = (0..2)
+        .map(|_| {
+            let mut tx = tx.clone();
+            thread::spawn(move|| {
+                for i in 0..COUNT {
+                    tx.send_blocking(i).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    for handle in senders {
+        handle.join().unwrap();
+    }
+
+    for handle in receivers {
+        assert_eq!(handle.join().unwrap(), COUNT * (COUNT - 1));
+    }
+}
@@ -1,8 +1,16 @@
-
 pub mod bounded;
-pub mod unordered;
+pub mod broadcast;
+pub mod pipeline;
+#[macro_use]
+pub mod select;
+pub mod unbounded;
+
+pub(crate) mod lock;
+pub(crate) mod waiters;
+mod park;
 
-pub use self::bounded::{queue, Sender, Receiver, SendError, RecvError};
+pub use self::bounded::{queue, Sender, Receiver, SendFuture, RecvFuture, Drain, SendSlot, RecvGuard};
+pub use self::bounded::{SendError, RecvError, SendTimeoutError, RecvTimeoutError, GrowError};
 
 #[cfg(test)]
 mod tests;
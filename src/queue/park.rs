@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+/// Thread parker which can be registered to the waiter queue as a `Waker`.
+#[derive(Debug)]
+pub(crate) struct Parker {
+    thread: Thread,
+    notified: AtomicBool,
+}
+
+impl Parker {
+    /// Create parker for current thread.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Parker {
+            thread: thread::current(),
+            notified: false.into(),
+        })
+    }
+
+    pub fn waker(self: &Arc<Self>) -> Waker {
+        Waker::from(Arc::clone(self))
+    }
+
+    /// Block current thread until notified.
+    pub fn park(&self) {
+        while !self.notified.swap(false, Ordering::Acquire) {
+            thread::park();
+        }
+    }
+
+    /// Block current thread until notified or the deadline is reached.
+    ///
+    /// Returns `false` if timed out.
+    pub fn park_until(&self, deadline: Instant) -> bool {
+        loop {
+            if self.notified.swap(false, Ordering::Acquire) {
+                return true;
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
+
+impl Wake for Parker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.notified.store(true, Ordering::Release);
+        self.thread.unpark();
+    }
+}
@@ -0,0 +1,217 @@
+//! Disruptor-style pipeline with dependent consumer stages.
+//!
+//! Stages process every message in place before receivers take them out of the buffer.
+//! Each stage can depend on other stages, so it only sees messages they have processed.
+//!
+//! ```
+//! use ringbuf::queue::pipeline::Builder;
+//! use ringbuf::sequence::owned::Owned;
+//!
+//! let mut builder = Builder::new(16);
+//! let journal = builder.stage(&[]);
+//! let replicate = builder.stage(&[]);
+//! let (mut tx, mut stages, mut rx) = builder.build::<Owned, Owned, u32>(&[journal, replicate]);
+//!
+//! tx.try_send(42).unwrap();
+//! assert!(rx.try_recv().is_err()); // Not processed by stages yet
+//!
+//! for stage in &mut stages {
+//!     assert_eq!(stage.try_process(|&msg| assert_eq!(msg, 42)), Ok(Some(1)));
+//! }
+//! assert_eq!(rx.try_recv(), Ok(Some(42)));
+//! ```
+
+use std::sync::Arc;
+use std::ops::Drop;
+use std::fmt;
+
+use role::Kind;
+use counter::{Counter, CounterRange};
+use buffer::Buffer;
+use sequence::{Sequence, Limit};
+use sequence::owned;
+use queue::park::Parker;
+use queue::bounded::{self, Sender, Receiver, RecvError};
+use queue::bounded::head::{Head, Gate};
+
+/// Builder of the pipeline topology.
+#[derive(Debug)]
+pub struct Builder {
+    capacity: usize,
+    gates: Vec<Arc<Gate>>,
+}
+
+/// Handle to refer a stage while building the pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageId(usize);
+
+/// Consumer stage which processes messages in place.
+pub struct Stage<S: Sequence, R: Sequence, T> {
+    head: Arc<Head<S, R>>,
+    gate: Arc<Gate>,
+    buf: Buffer<Arc<Head<S, R>>, T>,
+    cache: owned::Cache,
+}
+
+struct StageLimit<'a, S: Sequence + 'a, R: Sequence + 'a> {
+    head: &'a Head<S, R>,
+    gate: &'a Gate,
+}
+
+impl Builder {
+    pub fn new(capacity: usize) -> Self {
+        Builder {
+            capacity,
+            gates: Vec::new(),
+        }
+    }
+
+    /// Add a stage which processes messages after given stages,
+    /// or right after they're sent if none.
+    pub fn stage(&mut self, after: &[StageId]) -> StageId {
+        let after = after.iter().map(|id| self.gates[id.0].clone()).collect();
+        self.gates.push(Arc::new(Gate::new(after)));
+        StageId(self.gates.len() - 1)
+    }
+
+    /// Build the pipeline whose receivers take messages after given stages.
+    ///
+    /// Receivers also wait for stages which no other stage depends on, even if they're
+    /// not given, as messages should not be taken out while any stage may process them.
+    ///
+    /// Returned stages are in the order they're added.
+    /// Messages should be `Sync` as stages process them concurrently.
+    #[allow(clippy::type_complexity)]
+    pub fn build<S, R, T>(
+        self, after: &[StageId]
+    ) -> (Sender<S, R, T>, Vec<Stage<S, R, T>>, Receiver<S, R, T>) where
+        S: Sequence, R: Sequence, T: Sync
+    {
+        let mut gates: Vec<Arc<Gate>> = after.iter().map(|id| self.gates[id.0].clone()).collect();
+
+        for gate in &self.gates {
+            let is_leaf = !self.gates.iter()
+                .any(|other| other.after().iter().any(|prev| Arc::ptr_eq(prev, gate)));
+
+            if is_leaf && !gates.iter().any(|given| Arc::ptr_eq(given, gate)) {
+                gates.push(gate.clone());
+            }
+        }
+
+        let head = Head::with_gates(S::default(), R::default(), gates);
+        let buffer = Buffer::new(head.clone(), self.capacity);
+
+        let stages = self.gates.into_iter()
+            .map(|gate| Stage::new(head.clone(), gate, buffer.clone()))
+            .collect();
+        let (sender, receiver) = bounded::from_buffer(buffer);
+
+        (sender, stages, receiver)
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Stage<S, R, T> {
+    fn new(head: Arc<Head<S, R>>, gate: Arc<Gate>, buf: Buffer<Arc<Head<S, R>>, T>) -> Self {
+        // unwrap() is ok as each gate is given to single stage
+        let cache = gate.seq().cache(&StageLimit { head: &head, gate: &gate }).unwrap();
+
+        Stage {
+            head,
+            gate,
+            buf,
+            cache,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.head.sender().counter().fetch().is_err()
+    }
+
+    /// Process every messages available to this stage at once.
+    ///
+    /// Returns the number of processed messages, or `Ok(None)` if the channel
+    /// is closed and every messages are processed.
+    pub fn try_process<F: FnMut(&T)>(&mut self, mut f: F) -> Result<Option<usize>, RecvError> {
+        let range = match self.claim() {
+            Some(range) => range,
+            None if self.is_closed() => match self.claim() {
+                // Sender may advanced right before it's closed. Check it again
+                Some(range) => range,
+                None if self.gate.seq().fetch_last() == self.head.sender().fetch_last() => {
+                    return Ok(None);
+                }
+                None => return Err(RecvError),
+            },
+            None => return Err(RecvError),
+        };
+
+        for count in range {
+            // Slots abandoned by senders hold no message
+            if !self.head.skipped().contains(count) {
+                f(unsafe { &*self.buf.get(count) });
+            }
+        }
+
+        // Stage sequence is never closed
+        let _ = self.gate.seq().commit_n(&mut self.cache, range);
+        self.head.waiters().notify_every(Kind::Receive);
+
+        Ok(Some(range.len()))
+    }
+
+    /// Process available messages, blocking current thread while nothing is available.
+    ///
+    /// Returns `None` if the channel is closed and every messages are processed.
+    pub fn process<F: FnMut(&T)>(&mut self, mut f: F) -> Option<usize> {
+        let parker = Parker::new();
+
+        loop {
+            if let Ok(res) = self.try_process(&mut f) {
+                return res;
+            }
+
+            let key = self.head.waiters().wait(Kind::Receive, parker.waker());
+
+            // Recheck as upstream may advanced before registration.
+            // Pipelines notify every waiters at once, so nothing to pass on cancel.
+            if let Ok(res) = self.try_process(&mut f) {
+                self.head.waiters().cancel(key);
+                return res;
+            }
+
+            parker.park();
+        }
+    }
+
+    fn claim(&mut self) -> Option<CounterRange> {
+        let limit = StageLimit {
+            head: &self.head,
+            gate: &self.gate,
+        };
+
+        self.gate.seq().claim_n(&mut self.cache, &limit, self.buf.capacity())
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Drop for Stage<S, R, T> {
+    fn drop(&mut self) {
+        // Let downstream go on without this stage
+        self.gate.detach();
+        self.head.waiters().notify_every(Kind::Receive);
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> fmt::Debug for Stage<S, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Stage")
+            .field("gate", &self.gate)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl<'a, S: Sequence, R: Sequence> Limit for StageLimit<'a, S, R> {
+    fn count(&self) -> Counter {
+        Gate::limit(self.gate.after(), self.head.sender())
+    }
+}
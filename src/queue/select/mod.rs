@@ -0,0 +1,282 @@
+//! Wait for multiple operations on bounded channels at once.
+//!
+//! Each operation is added with a handler, and only the handler of the operation
+//! completed by the select is called. Other operations are left untouched,
+//! and messages of unselected send operations are dropped with the select.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate ringbuf;
+//!
+//! use ringbuf::queue::queue;
+//! use ringbuf::sequence::owned::Owned;
+//!
+//! # fn main() {
+//! let (mut tx1, mut rx1) = queue::<Owned, Owned, u32>(1);
+//! let (mut tx2, mut rx2) = queue::<Owned, Owned, &str>(1);
+//! tx2.try_send("hello").unwrap();
+//!
+//! let res = select! {
+//!     recv(rx1) -> msg => format!("rx1: {:?}", msg),
+//!     recv(rx2) -> msg => format!("rx2: {:?}", msg),
+//! };
+//! assert_eq!(res, "rx2: Some(\"hello\")");
+//!
+//! // Buffer of `tx2` is free now, but the one of `tx1` isn't.
+//! tx1.try_send(1).unwrap();
+//! let sent = select! {
+//!     send(tx1, 2) -> res => { assert!(res.is_ok()); 1 },
+//!     send(tx2, "world") -> res => { assert!(res.is_ok()); 2 },
+//! };
+//! assert_eq!(sent, 2);
+//! assert_eq!(rx1.try_recv(), Ok(Some(1)));
+//! assert_eq!(rx2.try_recv(), Ok(Some("world")));
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+use std::task::Waker;
+use std::fmt;
+
+use role::Kind;
+use sequence::Sequence;
+use queue::waiters::{WaitList, Key};
+use queue::park::Parker;
+use queue::bounded::{Sender, Receiver, SendError};
+
+/// Set of operations to complete one of them.
+///
+/// Operations are tried in the order they're added,
+/// so earlier ones are preferred if several of them are ready.
+/// Note that operations on closed channels are always ready.
+pub struct Select<'a, U> {
+    ops: Vec<Box<dyn Operation<U> + 'a>>,
+}
+
+trait Operation<U> {
+    /// Complete this operation and call its handler, or returns `None` if it's not ready.
+    ///
+    /// Receiving from closed channel or sending to it is also considered as ready.
+    fn try_complete(&mut self) -> Option<U>;
+
+    /// Waiter queue to be notified when this operation becomes ready.
+    fn waiters(&self) -> &WaitList<Waker>;
+
+    fn kind(&self) -> Kind;
+}
+
+struct RecvOp<'a, S: Sequence + 'a, R: Sequence + 'a, T: 'a, F> {
+    rx: &'a mut Receiver<S, R, T>,
+    handler: Option<F>,
+}
+
+struct SendOp<'a, S: Sequence + 'a, R: Sequence + 'a, T: 'a, F> {
+    tx: &'a mut Sender<S, R, T>,
+    msg: Option<T>,
+    handler: Option<F>,
+}
+
+impl<'a, U> Select<'a, U> {
+    pub fn new() -> Self {
+        Select {
+            ops: Vec::new(),
+        }
+    }
+
+    /// Add an operation to receive a message from `rx`.
+    ///
+    /// Handler is given `None` if the channel is closed and every messages are received.
+    pub fn recv<S, R, T, F>(mut self, rx: &'a mut Receiver<S, R, T>, handler: F) -> Self where
+        S: Sequence, R: Sequence, F: FnOnce(Option<T>) -> U + 'a
+    {
+        self.ops.push(Box::new(RecvOp {
+            rx,
+            handler: Some(handler),
+        }));
+        self
+    }
+
+    /// Add an operation to send `msg` to `tx`.
+    ///
+    /// Handler is given the error if the channel is closed.
+    pub fn send<S, R, T, F>(mut self, tx: &'a mut Sender<S, R, T>, msg: T, handler: F) -> Self where
+        S: Sequence, R: Sequence, F: FnOnce(Result<(), SendError<T>>) -> U + 'a
+    {
+        self.ops.push(Box::new(SendOp {
+            tx,
+            msg: Some(msg),
+            handler: Some(handler),
+        }));
+        self
+    }
+
+    /// Complete an operation which is ready now, and returns the output of its handler.
+    ///
+    /// Returns the select back if none of them is ready.
+    pub fn try_select(mut self) -> Result<U, Self> {
+        match self.try_complete() {
+            Some(res) => Ok(res),
+            None => Err(self),
+        }
+    }
+
+    /// Complete an operation, blocking current thread until any of them is ready.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no operation is added, as it would block forever.
+    pub fn select(self) -> U {
+        match self.select_until(None) {
+            Ok(res) => res,
+            Err(_) => unreachable!("Select without deadline never times out"),
+        }
+    }
+
+    /// Complete an operation, blocking current thread for at most `timeout`
+    /// until any of them is ready.
+    ///
+    /// Returns the select back if timed out.
+    pub fn select_timeout(self, timeout: Duration) -> Result<U, Self> {
+        // Too far deadline is same as no deadline
+        self.select_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Complete an operation, blocking current thread until `deadline`
+    /// until any of them is ready.
+    ///
+    /// Returns the select back if timed out.
+    pub fn select_deadline(self, deadline: Instant) -> Result<U, Self> {
+        self.select_until(Some(deadline))
+    }
+
+    fn select_until(mut self, deadline: Option<Instant>) -> Result<U, Self> {
+        assert!(!self.ops.is_empty(), "Select without any operation blocks forever");
+        let parker = Parker::new();
+
+        loop {
+            if let Some(res) = self.try_complete() {
+                return Ok(res);
+            }
+
+            // Single waker is shared by every channels
+            let keys: Vec<_> = self.ops.iter()
+                .map(|op| op.waiters().wait(op.kind(), parker.waker()))
+                .collect();
+
+            // Recheck as the counterparts may advanced before registration
+            let res = match self.try_complete() {
+                Some(res) => Some(res),
+                None => match deadline {
+                    None => {
+                        parker.park();
+                        None
+                    }
+                    Some(deadline) if !parker.park_until(deadline) => {
+                        self.cancel(keys);
+                        // Last chance before timeout
+                        return self.try_select();
+                    }
+                    Some(_) => None,
+                },
+            };
+
+            self.cancel(keys);
+
+            if let Some(res) = res {
+                return Ok(res);
+            }
+        }
+    }
+
+    fn try_complete(&mut self) -> Option<U> {
+        self.ops.iter_mut().filter_map(|op| op.try_complete()).next()
+    }
+
+    /// Deregister the waker from every channels.
+    fn cancel(&self, keys: Vec<Key>) {
+        for (op, key) in self.ops.iter().zip(keys) {
+            if !op.waiters().cancel(key) {
+                // Already notified, but it may not be used. Pass it to other waiter
+                op.waiters().notify(op.kind());
+            }
+        }
+    }
+}
+
+impl<'a, U> Default for Select<'a, U> {
+    fn default() -> Self {
+        Select::new()
+    }
+}
+
+impl<'a, U> fmt::Debug for Select<'a, U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Select")
+            .field("ops", &self.ops.len())
+            .finish()
+    }
+}
+
+impl<'a, S, R, T, U, F> Operation<U> for RecvOp<'a, S, R, T, F> where
+    S: Sequence, R: Sequence, F: FnOnce(Option<T>) -> U
+{
+    fn try_complete(&mut self) -> Option<U> {
+        let msg = self.rx.try_recv().ok()?;
+        let handler = self.handler.take().expect("Operation is already completed");
+        Some(handler(msg))
+    }
+
+    fn waiters(&self) -> &WaitList<Waker> {
+        self.rx.waiters()
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Receive
+    }
+}
+
+impl<'a, S, R, T, U, F> Operation<U> for SendOp<'a, S, R, T, F> where
+    S: Sequence, R: Sequence, F: FnOnce(Result<(), SendError<T>>) -> U
+{
+    fn try_complete(&mut self) -> Option<U> {
+        let msg = self.msg.take().expect("Operation is already completed");
+
+        let res = match self.tx.try_send(msg) {
+            Err(SendError::BufferFull(msg)) => {
+                self.msg = Some(msg);
+                return None;
+            }
+            res => res,
+        };
+
+        let handler = self.handler.take().expect("Operation is already completed");
+        Some(handler(res))
+    }
+
+    fn waiters(&self) -> &WaitList<Waker> {
+        self.tx.waiters()
+    }
+
+    fn kind(&self) -> Kind {
+        Kind::Send
+    }
+}
+
+/// Complete one of given operations on bounded channels, blocking current thread
+/// until any of them is ready.
+///
+/// Each branch is either `recv(rx) -> msg => body` or `send(tx, msg) -> res => body`,
+/// where `msg` and `res` are patterns to bind the result of the operation.
+/// Bodies are closures of the `Select` so they can't `return` or `break` outer scope,
+/// and the value of the selected one is returned.
+#[macro_export]
+macro_rules! select {
+    ($($op:ident($chan:expr $(, $arg:expr)*) -> $bind:pat => $body:expr),+ $(,)?) => {
+        $crate::queue::select::Select::new()
+            $(.$op(&mut $chan $(, $arg)*, |$bind| $body))+
+            .select()
+    };
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,150 @@
+use std::thread;
+use std::time::Duration;
+
+use sequence::owned::Owned;
+use sequence::shared::Shared;
+use queue::bounded::{queue, SendError};
+
+use super::Select;
+
+const COUNT: usize = 10_000;
+
+#[test]
+fn test_try_select() {
+    let (mut tx1, mut rx1) = queue::<Owned, Owned, usize>(2);
+    let (mut tx2, mut rx2) = queue::<Owned, Owned, usize>(2);
+
+    let sel = Select::new()
+        .recv(&mut rx1, |msg| (1, msg))
+        .recv(&mut rx2, |msg| (2, msg));
+    let sel = sel.try_select().unwrap_err();
+    drop(sel);
+
+    // Only the first ready one is completed
+    tx1.try_send(10).unwrap();
+    tx2.try_send(20).unwrap();
+    let res = Select::new()
+        .recv(&mut rx1, |msg| (1, msg))
+        .recv(&mut rx2, |msg| (2, msg))
+        .try_select();
+    assert_eq!(res.ok(), Some((1, Some(10))));
+    assert!(rx1.try_recv().is_err());
+    assert_eq!(rx2.try_recv(), Ok(Some(20)));
+}
+
+#[test]
+fn test_select_send() {
+    let (mut tx1, mut rx1) = queue::<Owned, Owned, usize>(1);
+    let (mut tx2, mut rx2) = queue::<Owned, Owned, usize>(1);
+    tx1.try_send(1).unwrap();
+
+    let res = select! {
+        send(tx1, 2) -> res => res.map(|()| 1),
+        send(tx2, 3) -> res => res.map(|()| 2),
+    };
+    assert_eq!(res, Ok(2));
+    assert_eq!(rx1.try_recv(), Ok(Some(1)));
+    assert!(rx1.try_recv().is_err());
+    assert_eq!(rx2.try_recv(), Ok(Some(3)));
+
+    drop(rx2);
+    let res = select! {
+        send(tx2, 4) -> res => res,
+    };
+    assert_eq!(res, Err(SendError::Disconnected(4)));
+}
+
+#[test]
+fn test_select_closed() {
+    let (tx1, mut rx1) = queue::<Owned, Owned, usize>(2);
+    let (mut tx2, mut rx2) = queue::<Owned, Owned, usize>(2);
+    drop(tx1);
+
+    // Closed channel is ready, with `None`
+    let res = select! {
+        recv(rx2) -> msg => (2, msg),
+        recv(rx1) -> msg => (1, msg),
+    };
+    assert_eq!(res, (1, None));
+
+    tx2.try_send(5).unwrap();
+    let res = select! {
+        recv(rx2) -> msg => (2, msg),
+        recv(rx1) -> msg => (1, msg),
+    };
+    assert_eq!(res, (2, Some(5)));
+}
+
+#[test]
+fn test_select_timeout() {
+    let (_tx1, mut rx1) = queue::<Owned, Owned, usize>(2);
+    let (mut tx2, _rx2) = queue::<Owned, Owned, usize>(1);
+    tx2.try_send(1).unwrap();
+
+    let sel = Select::new()
+        .recv(&mut rx1, |msg| msg)
+        .send(&mut tx2, 2, |res| res.ok().map(|()| 0));
+    assert!(sel.select_timeout(Duration::from_millis(10)).is_err());
+}
+
+#[test]
+fn test_select_blocking() {
+    let (mut tx1, mut rx1) = queue::<Owned, Owned, usize>(2);
+    let (mut tx2, mut rx2) = queue::<Owned, Owned, usize>(2);
+
+    let handle = thread::spawn(move|| {
+        thread::sleep(Duration::from_millis(10));
+        tx2.send_blocking(42).unwrap();
+        tx2
+    });
+
+    let res = select! {
+        recv(rx1) -> msg => (1, msg),
+        recv(rx2) -> msg => (2, msg),
+    };
+    assert_eq!(res, (2, Some(42)));
+
+    let _tx2 = handle.join().unwrap();
+    tx1.try_send(1).unwrap();
+    assert_eq!(rx1.try_recv(), Ok(Some(1)));
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_select_mpsc() {
+    let (tx1, mut rx1) = queue::<Shared, Owned, usize>(16);
+    let (tx2, mut rx2) = queue::<Shared, Owned, usize>(16);
+
+    let senders: Vec<_> = vec![tx1.clone(), tx1]
+        .into_iter()
+        .chain(vec![tx2.clone(), tx2])
+        .map(|mut tx| thread::spawn(move|| {
+            for i in 0..COUNT {
+                tx.send_blocking(i).unwrap();
+            }
+        }))
+        .collect();
+
+    let mut acc = 0;
+    let closed = loop {
+        let res = select! {
+            recv(rx1) -> msg => (0, msg),
+            recv(rx2) -> msg => (1, msg),
+        };
+        match res {
+            (_, Some(num)) => acc += num,
+            (idx, None) => break idx,
+        }
+    };
+
+    // Closed one is always ready, so receive the rest without select
+    let rest = if closed == 0 { &mut rx2 } else { &mut rx1 };
+    while let Some(num) = rest.recv_blocking() {
+        acc += num;
+    }
+
+    for handle in senders {
+        handle.join().unwrap();
+    }
+    assert_eq!(acc, 4 * COUNT * (COUNT - 1) / 2);
+}
@@ -1,12 +1,19 @@
 
 use std::thread;
 use std::time::Duration;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Wake, Waker, Context, Poll};
 
 use rand::{Rng, thread_rng};
 
 use sequence::owned::Owned;
 use sequence::shared::Shared;
 
+use test_util::LoudDrop;
+
 use super::bounded;
 
 #[cfg(not(feature = "ci"))]
@@ -71,8 +78,8 @@ fn test_spinning_mpmc() {
                     loop {
                         match tx.try_send(num) {
                             Ok(()) => break,
-                            Err(bounded::SendError::Closed(_)) => panic!("Boo, never!"),
                             Err(bounded::SendError::BufferFull(_)) => {}
+                            Err(_) => panic!("Boo, never!"),
                         }
                         if let Ok(()) = tx.try_send(num) {
                             break;
@@ -112,6 +119,8 @@ fn test_spinning_mpmc() {
 }
 
 #[test]
+// Lints of newer toolchains on the original test
+#[allow(dead_code, clippy::missing_const_for_thread_local)]
 fn test_drop_unsent() {
     use std::ops::Drop;
     use std::cell::Cell;
@@ -149,3 +158,1216 @@ fn test_drop_unsent() {
 
     assert_eq!(sent - received, DROP_COUNT.with(|count| count.get()));
 }
+
+#[test]
+fn test_blocking_spsc() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(SIZE);
+
+    let handle = thread::spawn(move|| {
+        for i in 0..COUNT {
+            tx.send_blocking(i).unwrap();
+        }
+    });
+
+    for i in 0..COUNT {
+        assert_eq!(rx.recv_blocking(), Some(i));
+    }
+
+    handle.join().unwrap();
+    assert_eq!(rx.recv_blocking(), None);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_blocking_mpmc() {
+    let (tx, rx) = bounded::queue::<Shared, Shared, u64>(SIZE);
+
+    let senders: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut tx = tx.clone();
+            thread::spawn(move|| {
+                let mut rng = thread_rng();
+                let mut acc = 0u64;
+
+                for _ in 0..COUNT {
+                    let num = rng.gen_range(0u64, 1024);
+                    acc += num;
+                    tx.send_blocking(num).unwrap();
+                }
+
+                acc
+            })
+        })
+        .collect();
+
+    let receivers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            thread::spawn(move|| {
+                let mut acc = 0u64;
+
+                for _ in 0..COUNT {
+                    acc += rx.recv_blocking().unwrap();
+                }
+
+                acc
+            })
+        })
+        .collect();
+
+    let tx_sum: u64 = senders.into_iter().map(|h| h.join().unwrap()).sum();
+    let rx_sum: u64 = receivers.into_iter().map(|h| h.join().unwrap()).sum();
+
+    assert_eq!(tx_sum, rx_sum);
+}
+
+#[test]
+fn test_blocking_wake_on_close() {
+    let (tx, mut rx) = bounded::queue::<Owned, Owned, usize>(2);
+
+    let handle = thread::spawn(move|| rx.recv_blocking());
+    thread::sleep(Duration::from_millis(10));
+    drop(tx);
+    assert_eq!(handle.join().unwrap(), None);
+
+    let (mut tx, rx) = bounded::queue::<Owned, Owned, usize>(2);
+    tx.try_send(1).unwrap();
+    tx.try_send(2).unwrap();
+
+    let handle = thread::spawn(move|| tx.send_blocking(3));
+    thread::sleep(Duration::from_millis(10));
+    drop(rx);
+    assert_eq!(handle.join().unwrap(), Err(bounded::SendError::Disconnected(3)));
+}
+
+#[test]
+fn test_disconnect() {
+    let (mut tx1, rx1) = bounded::queue::<Shared, Shared, usize>(2);
+    let mut tx2 = tx1.clone();
+    let rx2 = rx1.clone();
+
+    // Every receivers are dropped
+    drop(rx1);
+    assert!(!tx1.is_disconnected());
+    drop(rx2);
+    assert!(tx1.is_disconnected());
+    assert!(tx2.is_closed());
+    assert_eq!(tx1.try_send(1), Err(bounded::SendError::Disconnected(1)));
+    assert_eq!(tx2.send_timeout(2, Duration::from_secs(10)),
+        Err(bounded::SendTimeoutError::Disconnected(2)));
+
+    // Closed by a sender, not by receivers
+    let (mut tx1, rx) = bounded::queue::<Shared, Owned, usize>(2);
+    let mut tx2 = tx1.clone();
+    tx2.try_send(1).unwrap();
+    tx1.close();
+    assert!(!tx2.is_disconnected());
+    assert_eq!(tx2.try_send(2), Err(bounded::SendError::Closed(2)));
+    assert!(rx.is_disconnected());
+
+    drop(tx1);
+    drop(tx2);
+    assert!(rx.is_disconnected());
+}
+
+#[test]
+fn test_recv_after_close() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(4);
+    for i in 0..4 {
+        tx.try_send(i).unwrap();
+    }
+    drop(tx);
+
+    assert!(rx.is_closed());
+    assert!(rx.is_disconnected());
+    assert_eq!(rx.try_peek(), Ok(Some(0)));
+    assert_eq!(rx.try_recv(), Ok(Some(0)));
+    assert_eq!(*rx.try_recv_ref().unwrap().unwrap(), 1);
+    assert_eq!(rx.recv_blocking(), Some(2));
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(Some(3)));
+    assert_eq!(rx.try_recv(), Ok(None));
+    assert_eq!(rx.recv_blocking(), None);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_recv_after_drop_mpmc() {
+    let (tx, rx) = bounded::queue::<Shared, Shared, usize>(SIZE);
+
+    // Senders drop while others are in the middle of commit
+    let senders: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut tx = tx.clone();
+            thread::spawn(move|| {
+                for i in 0..COUNT {
+                    tx.send_blocking(i).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let receivers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            thread::spawn(move|| {
+                let mut received = 0;
+                loop {
+                    match rx.try_recv() {
+                        Ok(Some(_)) => received += 1,
+                        Ok(None) => return received,
+                        Err(bounded::RecvError) => thread::yield_now(),
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(rx);
+
+    for handle in senders {
+        handle.join().unwrap();
+    }
+
+    let received: usize = receivers.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(received, THREADS * COUNT);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_recv_after_close_mpmc() {
+    let (mut tx, rx) = bounded::queue::<Shared, Shared, usize>(SIZE);
+
+    // Commits in flight when closed fail, and others are received
+    let senders: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut tx = tx.clone();
+            thread::spawn(move|| {
+                let mut sent = 0;
+                loop {
+                    match tx.send_blocking(sent) {
+                        Ok(()) => sent += 1,
+                        // Receivers may be dropped after the close
+                        Err(bounded::SendError::Closed(_)) => return sent,
+                        Err(bounded::SendError::Disconnected(_)) => return sent,
+                        Err(e) => panic!("Unexpected error {:?}", e),
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let receivers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            thread::spawn(move|| {
+                let mut received = 0;
+                while rx.recv_blocking().is_some() {
+                    received += 1;
+                }
+                received
+            })
+        })
+        .collect();
+    drop(rx);
+
+    thread::sleep(Duration::from_millis(10));
+    tx.close();
+
+    let sent: usize = senders.into_iter().map(|h| h.join().unwrap()).sum();
+    let received: usize = receivers.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(sent, received);
+}
+
+#[test]
+fn test_introspection() {
+    let (mut tx, mut rx) = bounded::queue::<Shared, Shared, usize>(4);
+    assert_eq!(tx.capacity(), 4);
+    assert!(tx.is_empty() && rx.is_empty());
+
+    for i in 0..3 {
+        tx.try_send(i).unwrap();
+    }
+    assert_eq!((tx.len(), rx.len()), (3, 3));
+    assert!(!tx.is_full());
+    tx.try_send(3).unwrap();
+    assert!(tx.is_full() && rx.is_full());
+
+    assert_eq!(rx.try_recv(), Ok(Some(0)));
+    assert_eq!(rx.len(), 3);
+
+    let tx2 = tx.clone();
+    let rx2 = rx.clone();
+    assert_eq!((rx.sender_count(), rx.receiver_count()), (2, 2));
+    drop(tx2);
+    drop(rx2);
+    assert_eq!((tx.sender_count(), tx.receiver_count()), (1, 1));
+
+    drop(tx);
+    assert_eq!(rx.len(), 3);
+    assert_eq!(rx.sender_count(), 0);
+}
+
+#[test]
+fn test_non_power_of_two() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, LoudDrop>(3);
+    assert_eq!(tx.capacity(), 3);
+
+    for lap in 0..10 {
+        for i in 0..3 {
+            tx.try_send(LoudDrop(drops.clone(), lap * 3 + i)).unwrap();
+        }
+        assert!(tx.try_send(LoudDrop(drops.clone(), 0)).is_err());
+
+        for i in 0..2 {
+            assert_eq!(rx.try_recv().unwrap().unwrap().1, lap * 3 + i);
+        }
+        tx.try_send(LoudDrop(drops.clone(), 100)).unwrap();
+        assert_eq!(rx.try_recv().unwrap().unwrap().1, lap * 3 + 2);
+        assert_eq!(rx.try_recv().unwrap().unwrap().1, 100);
+    }
+    assert_eq!(drops.load(Ordering::Relaxed), 50);
+
+    // Messages left in the buffer are dropped with it
+    tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    drop(tx);
+    drop(rx);
+    assert_eq!(drops.load(Ordering::Relaxed), 52);
+}
+
+#[test]
+fn test_grow() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, LoudDrop>(2);
+    tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    tx.try_send(LoudDrop(drops.clone(), 1)).unwrap();
+
+    assert_eq!(tx.try_grow(2), Err(bounded::GrowError::InvalidCapacity));
+    assert_eq!(tx.try_grow(0), Err(bounded::GrowError::InvalidCapacity));
+
+    // Borrowed message is not moved
+    let first = rx.try_peek_ref().unwrap().unwrap();
+    tx.try_grow(3).unwrap();
+    assert_eq!(first.1, 0);
+    assert_eq!(tx.capacity(), 3);
+
+    tx.try_send(LoudDrop(drops.clone(), 2)).unwrap();
+    assert!(tx.is_full());
+    assert_eq!(rx.try_recv().unwrap().unwrap().1, 0);
+
+    tx.try_grow(8).unwrap();
+    for i in 3..9 {
+        tx.try_send(LoudDrop(drops.clone(), i)).unwrap();
+    }
+    assert_eq!(tx.len(), 8);
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+    // Messages are received in order across storages
+    for i in 1..5 {
+        assert_eq!(rx.try_recv().unwrap().unwrap().1, i);
+    }
+    assert_eq!(drops.load(Ordering::Relaxed), 5);
+
+    // Storages every messages are received from are freed
+    tx.try_grow(16).unwrap();
+    tx.try_send(LoudDrop(drops.clone(), 9)).unwrap();
+    assert_eq!(rx.try_recv().unwrap().unwrap().1, 5);
+
+    // Messages left in every storages are dropped with the channel
+    drop(rx);
+    assert_eq!(tx.try_grow(32), Err(bounded::GrowError::Closed));
+    drop(tx);
+    assert_eq!(drops.load(Ordering::Relaxed), 10);
+}
+
+#[test]
+fn test_grow_when_full() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(2);
+
+    tx.grow_when_full(Duration::from_secs(3600), 8);
+    tx.try_send(0).unwrap();
+    tx.try_send(1).unwrap();
+    assert_eq!(tx.try_send(2), Err(bounded::SendError::BufferFull(2)));
+    assert_eq!(tx.capacity(), 2);
+
+    // Grows right away once the buffer is found full
+    tx.grow_when_full(Duration::from_secs(0), 8);
+    for i in 2..8 {
+        tx.try_send(i).unwrap();
+    }
+    assert_eq!(tx.capacity(), 8);
+
+    // Not beyond the max capacity
+    assert_eq!(tx.try_send(8), Err(bounded::SendError::BufferFull(8)));
+    assert_eq!(tx.capacity(), 8);
+
+    for i in 0..8 {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+}
+
+#[test]
+fn test_grow_when_full_blocking() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(2);
+
+    tx.grow_when_full(Duration::from_millis(10), 4);
+    for i in 0..4 {
+        tx.send_timeout(i, Duration::from_secs(10)).unwrap();
+    }
+    assert_eq!(tx.capacity(), 4);
+
+    // Times out as it can't grow anymore
+    assert_eq!(
+        tx.send_timeout(4, Duration::from_millis(20)),
+        Err(bounded::SendTimeoutError::Timeout(4))
+    );
+
+    // Period is reset by successful send
+    assert_eq!(rx.try_recv(), Ok(Some(0)));
+    tx.send_blocking(4).unwrap();
+    assert_eq!(tx.capacity(), 4);
+
+    for i in 1..5 {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+}
+
+#[test]
+fn test_grow_spsc() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(2);
+
+    let handle = thread::spawn(move|| {
+        for i in 0..COUNT {
+            assert_eq!(rx.recv_blocking(), Some(i));
+        }
+        assert_eq!(rx.recv_blocking(), None);
+    });
+
+    for i in 0..COUNT {
+        if let Err(bounded::SendError::BufferFull(msg)) = tx.try_send(i) {
+            if tx.capacity() < 256 {
+                let capacity = tx.capacity() * 2;
+                tx.try_grow(capacity).unwrap();
+            }
+            tx.send_blocking(msg).unwrap();
+        }
+    }
+    drop(tx);
+
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_timeout() {
+    use std::time::Instant;
+
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(2);
+
+    let start = Instant::now();
+    assert_eq!(rx.recv_timeout(Duration::from_millis(20)),
+        Err(bounded::RecvTimeoutError::Timeout));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    tx.send_timeout(1, Duration::from_millis(20)).unwrap();
+    tx.send_deadline(2, Instant::now()).unwrap();
+
+    let start = Instant::now();
+    assert_eq!(tx.send_timeout(3, Duration::from_millis(20)),
+        Err(bounded::SendTimeoutError::Timeout(3)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    assert_eq!(rx.recv_deadline(Instant::now()), Ok(Some(1)));
+
+    let handle = thread::spawn(move|| {
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(rx.recv_blocking(), Some(2));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(Some(3)));
+        rx
+    });
+
+    tx.send_timeout(3, Duration::from_secs(10)).unwrap();
+    let mut rx = handle.join().unwrap();
+
+    drop(tx);
+    assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(None));
+}
+
+#[test]
+#[cfg(feature = "futures")]
+fn test_sink_stream() {
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    let (tx, rx) = bounded::queue::<Owned, Owned, usize>(SIZE);
+
+    let handle = thread::spawn(move|| {
+        block_on(stream::iter(0..COUNT).map(Ok).forward(tx)).unwrap();
+    });
+
+    let received: Vec<_> = block_on(rx.collect());
+    handle.join().unwrap();
+
+    assert_eq!(received, (0..COUNT).collect::<Vec<_>>());
+}
+
+#[test]
+#[cfg(feature = "futures")]
+fn test_sink_closed() {
+    use futures::executor::block_on;
+    use futures::SinkExt;
+
+    let (mut tx, rx) = bounded::queue::<Shared, Owned, usize>(2);
+    let mut tx2 = tx.clone();
+
+    block_on(SinkExt::send(&mut tx, 1)).unwrap();
+    block_on(SinkExt::send(&mut tx2, 2)).unwrap();
+
+    let handle = thread::spawn(move|| block_on(SinkExt::send(&mut tx, 3)));
+    thread::sleep(Duration::from_millis(10));
+    drop(rx);
+
+    assert_eq!(handle.join().unwrap(), Err(bounded::SendError::Disconnected(3)));
+    assert_eq!(block_on(SinkExt::send(&mut tx2, 4)), Err(bounded::SendError::Disconnected(4)));
+}
+
+#[test]
+#[cfg(feature = "futures")]
+fn test_sink_start_send_twice() {
+    use futures::Sink;
+
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(1);
+    tx.try_send(0).unwrap();
+
+    let mut tx = Pin::new(&mut tx);
+    tx.as_mut().start_send(1).unwrap();
+    assert_eq!(tx.as_mut().start_send(2), Err(bounded::SendError::BufferFull(2)));
+
+    let waker = Waker::noop();
+    let mut cx = Context::from_waker(waker);
+    assert!(tx.as_mut().poll_flush(&mut cx).is_pending());
+    assert_eq!(rx.try_recv(), Ok(Some(0)));
+    assert_eq!(tx.as_mut().poll_flush(&mut cx), Poll::Ready(Ok(())));
+    assert_eq!(rx.try_recv(), Ok(Some(1)));
+}
+
+/// Waker which counts how many times it's woken, and unparks the thread created it.
+struct TestWaker {
+    thread: thread::Thread,
+    woken: AtomicUsize,
+}
+
+impl TestWaker {
+    fn new() -> Arc<Self> {
+        Arc::new(TestWaker {
+            thread: thread::current(),
+            woken: 0.into(),
+        })
+    }
+
+    fn woken(&self) -> usize {
+        self.woken.load(Ordering::SeqCst)
+    }
+}
+
+impl Wake for TestWaker {
+    fn wake(self: Arc<Self>) {
+        self.woken.fetch_add(1, Ordering::SeqCst);
+        self.thread.unpark();
+    }
+}
+
+fn block_on<F: Future>(fut: F) -> F::Output {
+    let waker = Waker::from(TestWaker::new());
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = Box::pin(fut);
+
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(res) => return res,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn test_async_spsc() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(SIZE);
+
+    let handle = thread::spawn(move|| {
+        for i in 0..COUNT {
+            block_on(tx.send(i)).unwrap();
+        }
+    });
+
+    for i in 0..COUNT {
+        assert_eq!(block_on(rx.recv()), Some(i));
+    }
+
+    handle.join().unwrap();
+    assert_eq!(block_on(rx.recv()), None);
+}
+
+#[test]
+fn test_async_cancel() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, LoudDrop>(1);
+    tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+
+    let test_waker = TestWaker::new();
+    let waker = Waker::from(test_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // Dropping pending future drops its message exactly once
+    {
+        let mut fut = tx.send(LoudDrop(drops.clone(), 0));
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+    }
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    // Cancelled future gives back its message
+    let msg = {
+        let mut fut = tx.send(LoudDrop(drops.clone(), 0));
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+        fut.cancel().unwrap()
+    };
+    assert_eq!(drops.load(Ordering::SeqCst), 1);
+
+    // Cancelled futures are not woken anymore
+    drop(rx.try_recv().unwrap());
+    assert_eq!(drops.load(Ordering::SeqCst), 2);
+    assert_eq!(test_waker.woken(), 0);
+
+    // Same for receivers
+    {
+        let mut fut = rx.recv();
+        assert!(Pin::new(&mut fut).poll(&mut cx).is_pending());
+    }
+    tx.try_send(msg).unwrap();
+    assert_eq!(test_waker.woken(), 0);
+}
+
+#[test]
+fn test_send_many() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(8);
+
+    let (sent, rest) = tx.try_send_many(0..20);
+    assert_eq!(sent, 8);
+    assert_eq!(rest.len(), 12);
+
+    for i in 0..3 {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+
+    let (sent, rest) = tx.try_send_many(rest);
+    assert_eq!(sent, 3);
+    assert!(rest.eq(11..20));
+    assert_eq!(tx.try_send_slice(&[42]), 0);
+
+    for i in 3..11 {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+
+    assert_eq!(tx.try_send_slice(&[1, 2, 3]), 3);
+    drop(rx);
+    assert_eq!(tx.try_send_many(0..4).0, 0);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_send_many_mpmc() {
+    let (tx, rx) = bounded::queue::<Shared, Shared, u64>(SIZE);
+
+    let senders: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut tx = tx.clone();
+            thread::spawn(move|| {
+                let mut rng = thread_rng();
+                let nums: Vec<u64> = (0..COUNT).map(|_| rng.gen_range(0u64, 1024)).collect();
+                let mut rest = &nums[..];
+
+                while !rest.is_empty() {
+                    let sent = tx.try_send_slice(rest);
+                    rest = &rest[sent..];
+                }
+
+                nums.iter().sum::<u64>()
+            })
+        })
+        .collect();
+
+    let receivers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            thread::spawn(move|| {
+                let mut acc = 0u64;
+
+                for _ in 0..COUNT {
+                    acc += rx.recv_blocking().unwrap();
+                }
+
+                acc
+            })
+        })
+        .collect();
+
+    let tx_sum: u64 = senders.into_iter().map(|h| h.join().unwrap()).sum();
+    let rx_sum: u64 = receivers.into_iter().map(|h| h.join().unwrap()).sum();
+
+    assert_eq!(tx_sum, rx_sum);
+}
+
+#[test]
+fn test_send_many_panic() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let (mut tx, mut rx) = bounded::queue::<Shared, Owned, usize>(8);
+
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        tx.try_send_many((0..6).map(|i| if i == 3 { panic!("Boom") } else { i }))
+    }));
+    assert!(res.is_err());
+
+    // Messages written before panic are sent and the rest slots are given back
+    assert_eq!(tx.try_send_slice(&[10, 11]), 2);
+    assert!(!tx.is_closed());
+
+    for &i in &[0, 1, 2, 10, 11] {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+}
+
+#[test]
+fn test_send_many_wrong_len() {
+    // Iterator which claims more items than it yields
+    struct Liar(std::ops::Range<usize>);
+
+    impl Iterator for Liar {
+        type Item = usize;
+
+        fn next(&mut self) -> Option<usize> {
+            self.0.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (8, Some(8))
+        }
+    }
+
+    impl ExactSizeIterator for Liar {}
+
+    let (mut tx, mut rx) = bounded::queue::<Shared, Owned, usize>(8);
+    let mut tx2 = tx.clone();
+
+    assert_eq!(tx.try_send_many(Liar(0..3)).0, 3);
+    assert_eq!(tx2.try_send_slice(&[10]), 1);
+    assert!(!tx.is_closed());
+
+    for &i in &[0, 1, 2, 10] {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_send_many_wrong_len_mpmc() {
+    // Iterator which claims more items than it yields
+    struct Liar(std::ops::Range<usize>);
+
+    impl Iterator for Liar {
+        type Item = usize;
+
+        fn next(&mut self) -> Option<usize> {
+            self.0.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (SIZE, Some(SIZE))
+        }
+    }
+
+    let (mut tx, mut rx) = bounded::queue::<Shared, Owned, usize>(SIZE);
+    let mut tx2 = tx.clone();
+
+    let liar = thread::spawn(move|| {
+        let mut start = 0;
+
+        while start < COUNT {
+            let end = (start + 3).min(COUNT);
+            start += tx.try_send_many(Liar(start..end)).0;
+            assert!(!tx.is_closed());
+            thread::yield_now();
+        }
+    });
+
+    let honest = thread::spawn(move|| {
+        for i in 0..COUNT {
+            while let Err(bounded::SendError::BufferFull(_)) = tx2.try_send(i) {
+                thread::yield_now();
+            }
+        }
+    });
+
+    let mut sum = 0;
+    let mut received = 0;
+
+    while received < 2 * COUNT {
+        match rx.try_recv() {
+            Ok(Some(i)) => {
+                sum += i;
+                received += 1;
+            }
+            Ok(None) => panic!("Channel closed with {} messages left", 2 * COUNT - received),
+            Err(bounded::RecvError) => thread::yield_now(),
+        }
+    }
+
+    liar.join().unwrap();
+    honest.join().unwrap();
+
+    assert_eq!(sum, COUNT * (COUNT - 1));
+    assert_eq!(rx.try_recv(), Ok(None));
+}
+
+#[test]
+fn test_recv_many() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, usize>(8);
+    let mut buf = Vec::new();
+
+    assert_eq!(rx.try_recv_many(&mut buf, 4), 0);
+
+    assert_eq!(tx.try_send_many(0..6).0, 6);
+    assert_eq!(rx.try_recv_many(&mut buf, 4), 4);
+    assert_eq!(buf, [0, 1, 2, 3]);
+
+    assert_eq!(tx.try_send_many(6..10).0, 4);
+    assert_eq!(rx.try_recv_many(&mut buf, 100), 6);
+    assert_eq!(buf, (0..10).collect::<Vec<_>>());
+
+    assert_eq!(tx.try_send_many(10..12).0, 2);
+    drop(tx);
+    assert_eq!(rx.try_recv_many(&mut buf, 0), 0);
+    assert_eq!(rx.try_recv_many(&mut buf, 100), 2);
+    assert_eq!(rx.try_recv_many(&mut buf, 100), 0);
+    assert_eq!(rx.try_recv(), Ok(None));
+}
+
+#[test]
+fn test_drain() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = bounded::queue::<Owned, Shared, LoudDrop>(8);
+
+    assert_eq!(rx.drain().len(), 0);
+
+    for _ in 0..6 {
+        tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    }
+
+    {
+        let mut drain = rx.drain();
+        assert_eq!(drain.len(), 6);
+        drain.next().unwrap();
+        drain.next().unwrap();
+        assert_eq!(drain.len(), 4);
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+    }
+
+    // Messages not taken are dropped with the iterator, exactly once
+    assert_eq!(drops.load(Ordering::Relaxed), 6);
+    assert!(rx.try_recv().is_err());
+
+    for _ in 0..8 {
+        tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    }
+    assert_eq!(rx.drain().count(), 8);
+    assert_eq!(drops.load(Ordering::Relaxed), 14);
+
+    drop(rx);
+    drop(tx);
+    assert_eq!(drops.load(Ordering::Relaxed), 14);
+}
+
+#[test]
+fn test_drain_forget() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, LoudDrop>(8);
+
+    for i in 0..4 {
+        tx.try_send(LoudDrop(drops.clone(), i)).unwrap();
+    }
+
+    let mut drain = rx.drain();
+    assert_eq!(drain.next().unwrap().1, 0);
+    assert_eq!(drain.next().unwrap().1, 1);
+    std::mem::forget(drain);
+    assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+    // Messages not taken are left in the buffer
+    assert_eq!(rx.try_recv().unwrap().unwrap().1, 2);
+    drop((tx, rx));
+    assert_eq!(drops.load(Ordering::Relaxed), 4);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_drain_mpmc() {
+    let (tx, rx) = bounded::queue::<Shared, Shared, u64>(SIZE);
+
+    let senders: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut tx = tx.clone();
+            thread::spawn(move|| {
+                for i in 0..COUNT as u64 {
+                    tx.send_blocking(i).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let receivers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            let received = received.clone();
+            thread::spawn(move|| {
+                let mut acc = 0u64;
+                let mut buf = Vec::new();
+
+                while received.load(Ordering::Relaxed) < THREADS * COUNT {
+                    let mut count = 0;
+                    for num in rx.drain() {
+                        acc += num;
+                        count += 1;
+                    }
+                    count += rx.try_recv_many(&mut buf, SIZE / 2);
+                    received.fetch_add(count, Ordering::Relaxed);
+                }
+
+                acc + buf.into_iter().sum::<u64>()
+            })
+        })
+        .collect();
+    drop(rx);
+
+    for handle in senders {
+        handle.join().unwrap();
+    }
+
+    let total: u64 = receivers.into_iter()
+        .map(|handle| handle.join().unwrap())
+        .sum();
+    let count = COUNT as u64;
+    assert_eq!(total, THREADS as u64 * (count * (count - 1) / 2));
+}
+
+#[test]
+fn test_reserve() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, [u64; 32]>(2);
+
+    {
+        let mut slot = tx.try_reserve().unwrap();
+        let msg = slot.get_mut().as_mut_ptr();
+        for i in 0..32 {
+            unsafe {
+                (*msg)[i] = i as u64;
+            }
+        }
+        unsafe { slot.commit().unwrap(); }
+    }
+
+    // Uncommitted slot is given back
+    drop(tx.try_reserve().unwrap());
+    tx.try_reserve().unwrap().write([7; 32]).unwrap();
+    assert_eq!(tx.try_reserve().unwrap_err(), bounded::SendError::BufferFull(()));
+
+    let msg = rx.try_recv().unwrap().unwrap();
+    assert!(msg.iter().enumerate().all(|(i, &v)| v == i as u64));
+    assert_eq!(rx.try_recv(), Ok(Some([7; 32])));
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+
+    let slot = tx.try_reserve().unwrap();
+    drop(rx);
+    assert_eq!(slot.write([1; 32]), Err(bounded::SendError::Disconnected([1; 32])));
+    assert_eq!(tx.try_reserve().unwrap_err(), bounded::SendError::Disconnected(()));
+}
+
+#[test]
+fn test_reserve_mpmc() {
+    let (mut tx, mut rx) = bounded::queue::<Shared, Shared, String>(4);
+    let mut tx2 = tx.clone();
+    let mut buf = Vec::new();
+
+    // Abandoned slot is stepped over by receivers
+    let slot = tx.try_reserve().unwrap();
+    let slot2 = tx2.try_reserve().unwrap();
+    drop(slot);
+    slot2.write("a".to_string()).unwrap();
+    assert!(!tx.is_closed());
+    assert_eq!(rx.try_recv(), Ok(Some("a".to_string())));
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+
+    // Given back if no other slot is claimed after it
+    drop(tx.try_reserve().unwrap());
+    tx2.try_send("b".to_string()).unwrap();
+
+    let slot = tx.try_reserve().unwrap();
+    let slot2 = tx2.try_reserve().unwrap();
+    drop(slot);
+    slot2.write("c".to_string()).unwrap();
+    tx.try_send("d".to_string()).unwrap();
+    assert_eq!(rx.try_recv_many(&mut buf, 10), 3);
+    assert_eq!(buf, ["b", "c", "d"]);
+
+    // Abandoned slot is not dropped with the buffer
+    let slot = tx.try_reserve().unwrap();
+    let slot2 = tx2.try_reserve().unwrap();
+    drop(slot);
+    slot2.write("e".to_string()).unwrap();
+}
+
+#[test]
+fn test_reserve_forget() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, String>(4);
+
+    // Leaked slot is claimed again instead of being published
+    std::mem::forget(tx.try_reserve().unwrap());
+    tx.try_send("b".to_string()).unwrap();
+    assert_eq!(rx.try_recv(), Ok(Some("b".to_string())));
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+}
+
+#[test]
+fn test_recv_ref() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = bounded::queue::<Owned, Owned, LoudDrop>(2);
+
+    assert!(rx.try_recv_ref().is_err());
+
+    tx.try_send(LoudDrop(drops.clone(), 1)).unwrap();
+    tx.try_send(LoudDrop(drops.clone(), 2)).unwrap();
+    assert!(tx.try_send(LoudDrop(drops.clone(), 3)).is_err());
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+    {
+        let mut msg = rx.try_recv_ref().unwrap().unwrap();
+        assert_eq!(msg.1, 1);
+        msg.1 = 42;
+        assert_eq!(msg.1, 42);
+
+        // Slot is not given back yet
+        assert!(tx.try_send(LoudDrop(drops.clone(), 4)).is_err());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+    }
+
+    // Message is dropped with the guard
+    assert_eq!(drops.load(Ordering::Relaxed), 3);
+    tx.try_send(LoudDrop(drops.clone(), 5)).unwrap();
+
+    assert_eq!(rx.try_recv_ref().unwrap().unwrap().1, 2);
+    assert_eq!(rx.try_recv().unwrap().unwrap().1, 5);
+    assert_eq!(drops.load(Ordering::Relaxed), 5);
+
+    drop(tx);
+    assert!(rx.try_recv_ref().unwrap().is_none());
+}
+
+#[test]
+fn test_peek() {
+    let (mut tx, mut rx) = bounded::queue::<Shared, Owned, String>(4);
+
+    assert_eq!(rx.try_peek(), Err(bounded::RecvError));
+
+    tx.try_send("foo".to_string()).unwrap();
+    tx.try_send("bar".to_string()).unwrap();
+
+    assert_eq!(rx.try_peek(), Ok(Some("foo".to_string())));
+    assert_eq!(rx.try_peek_ref(), Ok(Some(&"foo".to_string())));
+    assert_eq!(rx.try_recv(), Ok(Some("foo".to_string())));
+    assert_eq!(rx.try_peek_ref().unwrap().map(String::as_str), Some("bar"));
+
+    drop(tx);
+    assert_eq!(rx.try_peek(), Ok(Some("bar".to_string())));
+    assert_eq!(rx.try_recv(), Ok(Some("bar".to_string())));
+    assert_eq!(rx.try_peek(), Ok(None));
+}
+
+#[test]
+fn test_force_send() {
+    let (mut tx, mut rx) = bounded::queue::<Owned, Shared, usize>(4);
+
+    for i in 0..4 {
+        assert_eq!(tx.force_send(i), Ok(None));
+    }
+    assert_eq!(tx.force_send(4), Ok(Some(0)));
+    assert_eq!(tx.force_send(5), Ok(Some(1)));
+
+    assert_eq!(rx.try_recv(), Ok(Some(2)));
+    assert_eq!(tx.force_send(6), Ok(None));
+    assert_eq!(tx.force_send(7), Ok(Some(3)));
+
+    for i in 4..8 {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+
+    drop(rx);
+    assert_eq!(tx.force_send(8), Err(bounded::SendError::Disconnected(8)));
+}
+
+#[test]
+fn test_force_send_overwriting() {
+    let (mut tx, mut rx) = bounded::overwriting_queue::<Owned, usize>(2);
+
+    for i in 0..2 {
+        assert_eq!(tx.force_send(i), Ok(None));
+    }
+    assert_eq!(tx.force_send(2), Ok(Some(0)));
+
+    assert_eq!(rx.try_recv(), Ok(Some(1)));
+    assert_eq!(tx.force_send(3), Ok(None));
+    assert_eq!(tx.force_send(4), Ok(Some(2)));
+
+    let mut buf = Vec::new();
+    assert_eq!(rx.try_recv_many(&mut buf, 10), 2);
+    assert_eq!(buf, [3, 4]);
+
+    drop(rx);
+    assert_eq!(tx.force_send(5), Err(bounded::SendError::Disconnected(5)));
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_force_send_mpmc() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = bounded::queue::<Shared, Shared, LoudDrop>(SIZE);
+
+    let senders: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut tx = tx.clone();
+            let drops = drops.clone();
+            thread::spawn(move|| {
+                for _ in 0..COUNT {
+                    tx.force_send(LoudDrop(drops.clone(), 0)).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let receivers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            thread::spawn(move|| {
+                while rx.recv_blocking().is_some() {}
+            })
+        })
+        .collect();
+    drop(rx);
+
+    for handle in senders.into_iter().chain(receivers) {
+        handle.join().unwrap();
+    }
+
+    // Every message is dropped exactly once, whether it's received, evicted or left
+    assert_eq!(drops.load(Ordering::Relaxed), THREADS * COUNT);
+}
+
+#[test]
+fn test_pipeline() {
+    use super::pipeline::Builder;
+
+    let mut builder = Builder::new(4);
+    let first = builder.stage(&[]);
+    let second = builder.stage(&[first]);
+    let (mut tx, mut stages, mut rx) = builder.build::<Owned, Owned, usize>(&[second]);
+
+    assert_eq!(tx.try_send_many(0..4).0, 4);
+    assert_eq!(stages[1].try_process(|_| panic!("Processed before first stage")),
+        Err(bounded::RecvError));
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+
+    let mut seen = Vec::new();
+    assert_eq!(stages[0].try_process(|&msg| seen.push(msg)), Ok(Some(4)));
+    assert_eq!(seen, [0, 1, 2, 3]);
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+
+    // Dropped stage doesn't gate others anymore
+    stages.pop();
+    for i in 0..4 {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+
+    tx.try_send(4).unwrap();
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+    assert_eq!(stages[0].try_process(|&msg| assert_eq!(msg, 4)), Ok(Some(1)));
+    drop(tx);
+    assert_eq!(stages[0].try_process(|_| {}), Ok(None));
+}
+
+#[test]
+fn test_pipeline_dangling_stage() {
+    use super::pipeline::Builder;
+
+    // Stage not given to `build` still gates receivers
+    let mut builder = Builder::new(1);
+    builder.stage(&[]);
+    let (mut tx, mut stages, mut rx) = builder.build::<Owned, Owned, String>(&[]);
+
+    tx.try_send("a".to_string()).unwrap();
+    assert_eq!(rx.try_recv(), Err(bounded::RecvError));
+    assert!(tx.try_send("b".to_string()).is_err());
+
+    assert_eq!(stages[0].try_process(|msg| assert_eq!(msg, "a")), Ok(Some(1)));
+    assert_eq!(rx.try_recv(), Ok(Some("a".to_string())));
+    tx.try_send("b".to_string()).unwrap();
+    assert_eq!(stages[0].try_process(|msg| assert_eq!(msg, "b")), Ok(Some(1)));
+    assert_eq!(rx.try_recv(), Ok(Some("b".to_string())));
+}
+
+#[test]
+fn test_pipeline_diamond() {
+    use super::pipeline::Builder;
+
+    let flags: Arc<Vec<AtomicUsize>> = Arc::new((0..COUNT).map(|_| AtomicUsize::new(0)).collect());
+
+    let mut builder = Builder::new(SIZE);
+    let journal = builder.stage(&[]);
+    let replicate = builder.stage(&[]);
+    let (mut tx, stages, mut rx) = builder.build::<Owned, Owned, usize>(&[journal, replicate]);
+
+    let handles: Vec<_> = stages.into_iter()
+        .enumerate()
+        .map(|(idx, mut stage)| {
+            let flags = flags.clone();
+            thread::spawn(move|| {
+                while stage.process(|&msg| {
+                    flags[msg].fetch_or(1 << idx, Ordering::Relaxed);
+                }).is_some() {}
+            })
+        })
+        .collect();
+
+    let sender = thread::spawn(move|| {
+        for i in 0..COUNT {
+            tx.send_blocking(i).unwrap();
+        }
+    });
+
+    for i in 0..COUNT {
+        assert_eq!(rx.recv_blocking(), Some(i));
+        assert_eq!(flags[i].load(Ordering::Relaxed), 0b11);
+    }
+
+    // Receiver waits for stages even after senders are gone
+    sender.join().unwrap();
+    assert_eq!(rx.recv_blocking(), None);
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
@@ -0,0 +1,310 @@
+//! Unbounded channel whose buffer is a list of fixed-size segments.
+//!
+//! Senders append new segments instead of waiting for receivers,
+//! and segments are freed once receivers pass them.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering};
+use std::task::Waker;
+use std::ops::Drop;
+use std::ptr;
+
+use role::Kind;
+use counter::{Counter, COUNTER_VALID_RANGE};
+use sequence::{Sequence, MultiCache, Offset, CommitError};
+use queue::bounded::{SendError, RecvError};
+use queue::waiters::WaitList;
+use queue::park::Parker;
+
+mod segment;
+
+use self::segment::{Segments, Cache, SEGMENT_SIZE};
+
+/// Senders can be ahead of receivers up to this, to keep counters comparable.
+const MAX_LEN: usize = COUNTER_VALID_RANGE / 2;
+
+#[derive(Debug)]
+struct Head<S: Sequence, R: Sequence, T> {
+    sender: S,
+    receiver: R,
+    segments: Segments<T>,
+    sender_count: AtomicUsize,
+    receiver_count: AtomicUsize,
+    senders_closed: AtomicBool,
+    receivers_closed: AtomicBool,
+    waiters: WaitList<Waker>,
+}
+
+#[derive(Debug)]
+pub struct Sender<S: Sequence, R: Sequence, T> {
+    head: Arc<Head<S, R, T>>,
+    /// Sequence cache of this sender, or `None` if it's created after the channel is closed.
+    cache: Option<S::Cache>,
+    segment: Cache<T>,
+    closed: bool,
+}
+
+#[derive(Debug)]
+pub struct Receiver<S: Sequence, R: Sequence, T> {
+    head: Arc<Head<S, R, T>>,
+    /// Sequence cache of this receiver, or `None` if it's created after the channel is closed.
+    cache: Option<R::Cache>,
+    segment: Cache<T>,
+    closed: bool,
+}
+
+pub fn channel<S, R, T>() -> (Sender<S, R, T>, Receiver<S, R, T>) where
+    S: Sequence, R: Sequence
+{
+    let head = Arc::new(Head {
+        sender: S::default(),
+        receiver: R::default(),
+        segments: Segments::new(),
+        sender_count: 0.into(),
+        receiver_count: 0.into(),
+        senders_closed: false.into(),
+        receivers_closed: false.into(),
+        waiters: WaitList::new(),
+    });
+
+    (Sender::new(head.clone()), Receiver::new(head))
+}
+
+impl<S: Sequence, R: Sequence, T> Head<S, R, T> {
+    fn is_closed(&self) -> bool {
+        self.sender.counter().fetch().is_err()
+    }
+
+    /// Close the channel from given side and wake up every blocked receivers.
+    ///
+    /// Like bounded channels, receiver counter is never closed.
+    fn close(&self, kind: Kind) {
+        match kind {
+            Kind::Send => self.senders_closed.store(true, Ordering::Release),
+            Kind::Receive => self.receivers_closed.store(true, Ordering::Release),
+        }
+
+        self.sender.counter().close();
+        self.waiters.notify_all();
+    }
+
+    /// Leave the channel, and close it if it was the last one of given side.
+    fn leave(&self, kind: Kind) {
+        let count = match kind {
+            Kind::Send => &self.sender_count,
+            Kind::Receive => &self.receiver_count,
+        };
+
+        if count.fetch_sub(1, Ordering::Release) == 1 {
+            self.close(kind);
+        }
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Drop for Head<S, R, T> {
+    fn drop(&mut self) {
+        let range = Counter::range(self.receiver.fetch_last(), self.sender.fetch_last());
+
+        unsafe {
+            self.segments.drop_range(range);
+        }
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Sender<S, R, T> {
+    fn new(head: Arc<Head<S, R, T>>) -> Self {
+        let cache = head.sender.cache(&Offset(head.receiver.counter(), MAX_LEN)).ok();
+
+        if cache.is_some() {
+            head.sender_count.fetch_add(1, Ordering::Release);
+        }
+
+        Sender {
+            head,
+            cache,
+            segment: Cache::default(),
+            closed: false,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed || self.head.is_closed()
+    }
+
+    /// Whether every receivers are dropped or closed the channel.
+    pub fn is_disconnected(&self) -> bool {
+        self.head.receivers_closed.load(Ordering::Acquire)
+    }
+
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.head.close(Kind::Send);
+    }
+
+    /// Send a message. It only fails if the channel is closed,
+    /// as new segment is appended when the buffer is full.
+    pub fn try_send(&mut self, msg: T) -> Result<(), SendError<T>> {
+        let cache = match &mut self.cache {
+            Some(cache) if !self.closed && !self.head.is_closed() => cache,
+            _ => return Err(self.closed_error(msg)),
+        };
+
+        let limit = Offset(self.head.receiver.counter(), MAX_LEN);
+        let count = self.head.sender.claim(cache, &limit)
+            .expect("Too many messages are sent but not received");
+        let slot = unsafe { self.head.segments.get(&mut self.segment, count) };
+
+        unsafe {
+            ptr::write(slot, msg);
+        }
+
+        match self.head.sender.commit(cache, count) {
+            Ok(()) => {
+                self.head.waiters.notify(Kind::Receive);
+                Ok(())
+            }
+            Err(CommitError) => {
+                self.closed = true;
+                let msg = unsafe { ptr::read(slot) };
+                Err(self.closed_error(msg))
+            }
+        }
+    }
+
+    /// Same as `try_send`, as sending never blocks.
+    pub fn send(&mut self, msg: T) -> Result<(), SendError<T>> {
+        self.try_send(msg)
+    }
+
+    fn closed_error(&self, msg: T) -> SendError<T> {
+        if self.is_disconnected() {
+            SendError::Disconnected(msg)
+        } else {
+            SendError::Closed(msg)
+        }
+    }
+}
+
+impl<S: MultiCache, R: Sequence, T> Clone for Sender<S, R, T> {
+    fn clone(&self) -> Self {
+        Sender::new(self.head.clone())
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Drop for Sender<S, R, T> {
+    fn drop(&mut self) {
+        if self.cache.is_some() {
+            self.head.leave(Kind::Send);
+        }
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Receiver<S, R, T> {
+    fn new(head: Arc<Head<S, R, T>>) -> Self {
+        let cache = head.receiver.cache(head.sender.counter()).ok();
+
+        if cache.is_some() {
+            head.receiver_count.fetch_add(1, Ordering::Release);
+        }
+
+        Receiver {
+            head,
+            cache,
+            segment: Cache::default(),
+            closed: false,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed || self.head.is_closed()
+    }
+
+    /// Whether every senders are dropped or closed the channel.
+    ///
+    /// Messages sent before that can still be received.
+    pub fn is_disconnected(&self) -> bool {
+        self.head.senders_closed.load(Ordering::Acquire)
+    }
+
+    /// Close the channel. Remaining messages are left to other receivers.
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.head.close(Kind::Receive);
+    }
+
+    /// Receive a message if available.
+    ///
+    /// Returns `Ok(None)` if the channel is closed and every messages are received.
+    pub fn try_recv(&mut self) -> Result<Option<T>, RecvError> {
+        let head = &*self.head;
+        let cache = match &mut self.cache {
+            Some(cache) if !self.closed => cache,
+            _ => return Ok(None),
+        };
+
+        let count = match head.receiver.claim(cache, head.sender.counter()) {
+            Some(count) => count,
+            // Senders may advanced right before it's closed. Check it again
+            None if head.is_closed() => match head.receiver.claim(cache, head.sender.counter()) {
+                Some(count) => count,
+                None => return Ok(None),
+            },
+            None => return Err(RecvError),
+        };
+
+        let msg = unsafe { ptr::read(head.segments.get(&mut self.segment, count)) };
+
+        // Receiver sequence is never closed
+        let _ = head.receiver.commit(cache, count);
+
+        // Receivers commit in order, so the segment is passed by every receivers
+        if (count + 1) & (SEGMENT_SIZE - 1) == 0 {
+            head.segments.release(head.receiver.fetch_last());
+        }
+
+        Ok(Some(msg))
+    }
+
+    /// Receive a message, blocking current thread while the buffer is empty.
+    ///
+    /// Returns `None` if the channel is closed and every messages are received.
+    pub fn recv_blocking(&mut self) -> Option<T> {
+        let parker = Parker::new();
+
+        loop {
+            if let Ok(res) = self.try_recv() {
+                return res;
+            }
+
+            let key = self.head.waiters.wait(Kind::Receive, parker.waker());
+
+            // Recheck as senders may advanced before registration
+            if let Ok(res) = self.try_recv() {
+                if !self.head.waiters.cancel(key) {
+                    // Already notified. Pass it to other waiter
+                    self.head.waiters.notify(Kind::Receive);
+                }
+                return res;
+            }
+
+            parker.park();
+        }
+    }
+}
+
+impl<S: Sequence, R: MultiCache, T> Clone for Receiver<S, R, T> {
+    fn clone(&self) -> Self {
+        Receiver::new(self.head.clone())
+    }
+}
+
+impl<S: Sequence, R: Sequence, T> Drop for Receiver<S, R, T> {
+    fn drop(&mut self) {
+        if self.cache.is_some() {
+            self.head.leave(Kind::Receive);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,158 @@
+
+use std::collections::VecDeque;
+use std::ptr;
+use std::fmt;
+
+use counter::{Counter, CounterRange};
+use queue::lock::SpinLock;
+
+/// Number of slots per segment.
+pub(crate) const SEGMENT_SIZE: usize = 32;
+
+/// Fixed-size slots which hold messages from `start`.
+pub(crate) struct Segment<T> {
+    /// Owns the memory `ptr` points to.
+    _slots: Vec<T>,
+    ptr: *mut T,
+    start: Counter,
+}
+
+/// List of segments which covers every slots not received yet.
+///
+/// Segments are appended when senders pass the last one,
+/// and freed when receivers pass them.
+pub(crate) struct Segments<T> {
+    list: SpinLock<List<T>>,
+}
+
+struct List<T> {
+    /// Start of the first segment in the list.
+    base: Counter,
+    segments: VecDeque<Box<Segment<T>>>,
+}
+
+/// Segment which is used recently by a half.
+///
+/// Segment is only freed after every its slots are received, so the cached one is
+/// valid while the half has a slot in it to send or receive.
+/// Its range is checked with cached `start`, without touching possibly freed segment.
+pub(crate) struct Cache<T> {
+    start: Counter,
+    segment: *const Segment<T>,
+}
+
+unsafe impl<T: Send> Send for Segment<T> {}
+unsafe impl<T: Send> Send for Cache<T> {}
+unsafe impl<T: Send> Sync for Cache<T> {}
+
+fn segment_start(count: Counter) -> Counter {
+    count - (count & (SEGMENT_SIZE - 1))
+}
+
+impl<T> Segment<T> {
+    fn new(start: Counter) -> Box<Self> {
+        let mut slots = Vec::with_capacity(SEGMENT_SIZE);
+        let ptr = slots.as_mut_ptr();
+
+        Box::new(Segment {
+            _slots: slots,
+            ptr,
+            start,
+        })
+    }
+
+    fn get(&self, count: Counter) -> *mut T {
+        debug_assert!(segment_start(count) == self.start);
+
+        unsafe {
+            self.ptr.add(count & (SEGMENT_SIZE - 1))
+        }
+    }
+}
+
+impl<T> Segments<T> {
+    pub fn new() -> Self {
+        Segments {
+            list: SpinLock::new(List {
+                base: Counter::default(),
+                segments: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Returns the slot of given counter, appending segments to cover it if needed.
+    ///
+    /// # Safety
+    ///
+    /// Slot of given counter should not be received yet.
+    pub unsafe fn get(&self, cache: &mut Cache<T>, count: Counter) -> *mut T {
+        let start = segment_start(count);
+
+        if cache.segment.is_null() || cache.start != start {
+            let mut list = self.list.lock();
+            debug_assert!(start >= list.base, "Segment is already freed");
+            let idx = ((start - list.base) as usize) / SEGMENT_SIZE;
+
+            while list.segments.len() <= idx {
+                let next = list.base + list.segments.len() * SEGMENT_SIZE;
+                list.segments.push_back(Segment::new(next));
+            }
+
+            cache.start = start;
+            cache.segment = &*list.segments[idx];
+        }
+
+        (*cache.segment).get(count)
+    }
+
+    /// Free segments every slots of which are received.
+    pub fn release(&self, receiver_last: Counter) {
+        let mut list = self.list.lock();
+
+        while list.base + SEGMENT_SIZE <= receiver_last && !list.segments.is_empty() {
+            list.segments.pop_front();
+            list.base += SEGMENT_SIZE;
+        }
+    }
+
+    /// Drop messages in given range, which are sent but not received.
+    ///
+    /// # Safety
+    ///
+    /// Messages in the range should not be accessed anymore.
+    pub unsafe fn drop_range(&self, range: CounterRange) {
+        let mut cache = Cache::default();
+
+        for count in range {
+            ptr::drop_in_place(self.get(&mut cache, count));
+        }
+    }
+}
+
+impl<T> Default for Cache<T> {
+    fn default() -> Self {
+        Cache {
+            start: Counter::default(),
+            segment: ptr::null(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Segments<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = self.list.lock();
+
+        f.debug_struct("Segments")
+            .field("base", &list.base)
+            .field("len", &list.segments.len())
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for Cache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("start", &self.start)
+            .finish()
+    }
+}
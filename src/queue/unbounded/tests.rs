@@ -0,0 +1,116 @@
+use std::thread;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use sequence::owned::Owned;
+use sequence::shared::Shared;
+use queue::bounded::{SendError, RecvError};
+use test_util::LoudDrop;
+
+use super::channel;
+use super::segment::SEGMENT_SIZE;
+
+const COUNT: usize = 10_000;
+const THREADS: usize = 4;
+
+#[test]
+fn test_never_full() {
+    let (mut tx, mut rx) = channel::<Owned, Owned, usize>();
+    assert_eq!(rx.try_recv(), Err(RecvError));
+
+    for i in 0..SEGMENT_SIZE * 3 + 1 {
+        tx.try_send(i).unwrap();
+    }
+
+    for i in 0..SEGMENT_SIZE * 3 + 1 {
+        assert_eq!(rx.try_recv(), Ok(Some(i)));
+    }
+    assert_eq!(rx.try_recv(), Err(RecvError));
+
+    // Segments passed by receivers are freed
+    assert_eq!(format!("{:?}", rx.head.segments),
+        format!("Segments {{ base: Counter({}), len: 1 }}", SEGMENT_SIZE * 3));
+
+    drop(tx);
+    assert!(rx.is_disconnected());
+    assert_eq!(rx.try_recv(), Ok(None));
+}
+
+#[test]
+fn test_drop_unreceived() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = channel::<Owned, Owned, LoudDrop>();
+
+    for _ in 0..SEGMENT_SIZE * 2 {
+        tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    }
+    for _ in 0..SEGMENT_SIZE + 1 {
+        assert!(rx.try_recv().unwrap().is_some());
+    }
+    assert_eq!(drops.load(Ordering::Relaxed), SEGMENT_SIZE + 1);
+
+    // Buffered messages are received after senders are dropped
+    drop(tx);
+    assert!(rx.try_recv().unwrap().is_some());
+
+    drop(rx);
+    assert_eq!(drops.load(Ordering::Relaxed), SEGMENT_SIZE * 2);
+}
+
+#[test]
+fn test_disconnect() {
+    let (mut tx1, rx) = channel::<Shared, Owned, usize>();
+    let mut tx2 = tx1.clone();
+
+    tx1.close();
+    assert_eq!(tx2.try_send(1), Err(SendError::Closed(1)));
+
+    let (mut tx, rx2) = channel::<Owned, Shared, usize>();
+    let rx3 = rx2.clone();
+    drop(rx2);
+    assert!(!tx.is_disconnected());
+    drop(rx3);
+    assert!(tx.is_disconnected());
+    assert_eq!(tx.send(1), Err(SendError::Disconnected(1)));
+
+    drop(rx);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_mpmc() {
+    let (tx, rx) = channel::<Shared, Shared, usize>();
+
+    let senders: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut tx = tx.clone();
+            thread::spawn(move|| {
+                for i in 0..COUNT {
+                    tx.send(i).unwrap();
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    let receivers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let mut rx = rx.clone();
+            thread::spawn(move|| {
+                let mut acc = 0;
+                while let Some(num) = rx.recv_blocking() {
+                    acc += num;
+                }
+                acc
+            })
+        })
+        .collect();
+    drop(rx);
+
+    for handle in senders {
+        handle.join().unwrap();
+    }
+
+    let total: usize = receivers.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(total, THREADS * COUNT * (COUNT - 1) / 2);
+}
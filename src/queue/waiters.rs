@@ -0,0 +1,187 @@
+use std::sync::atomic::{AtomicUsize, Ordering, fence};
+use std::task::Waker;
+use std::mem;
+use std::fmt;
+
+use role::Kind;
+use queue::lock::SpinLock;
+
+pub(crate) trait Notify {
+    /// Resume execution context.
+    ///
+    /// It should be no-op to notify currently executing context.
+    fn notify(self);
+}
+
+impl Notify for Waker {
+    fn notify(self) {
+        self.wake()
+    }
+}
+
+/// List of waiters, each tagged with the `Kind` of operation it waits for.
+///
+/// Waiters are not ordered. Any waiter of given kind can be picked on notification.
+/// Timed out `send_timeout` and dropped futures can take their waiter back by its `Key`,
+/// so a notification is passed to other waiters rather than spent on them.
+pub(crate) struct WaitList<T: Notify> {
+    waiting: [AtomicUsize; 2],
+    list: SpinLock<List<T>>,
+}
+
+/// Registration handle returned by `WaitList::wait`.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Key(usize);
+
+struct List<T> {
+    next_key: usize,
+    entries: Vec<Entry<T>>,
+}
+
+struct Entry<T> {
+    key: usize,
+    kind: Kind,
+    value: T,
+}
+
+fn slot(kind: Kind) -> usize {
+    match kind {
+        Kind::Send => 0,
+        Kind::Receive => 1,
+    }
+}
+
+impl<T: Notify> WaitList<T> {
+    pub fn new() -> Self {
+        WaitList {
+            waiting: [0.into(), 0.into()],
+            list: SpinLock::new(List {
+                next_key: 0,
+                entries: Vec::new(),
+            }),
+        }
+    }
+
+    /// Number of currently registered waiters of given kind.
+    pub fn len(&self, kind: Kind) -> usize {
+        self.waiting[slot(kind)].load(Ordering::Acquire)
+    }
+
+    /// Register `value` as a waiter of given kind.
+    ///
+    /// Caller should re-check its condition after registration and before suspending,
+    /// as notifications sent before this call are not delivered.
+    pub fn wait(&self, kind: Kind, value: T) -> Key {
+        let key = {
+            let mut list = self.list.lock();
+            let key = list.next_key;
+            list.next_key = key.wrapping_add(1);
+            list.entries.push(Entry {
+                key,
+                kind,
+                value,
+            });
+            self.waiting[slot(kind)].fetch_add(1, Ordering::SeqCst);
+            key
+        };
+
+        // Pairs with the sequentially consistent state change before `notify`.
+        // Either the waiter observes the state change, or the notifier observes the waiter.
+        // Waiting is the slow path anyway, so the fence is paid here rather than on notify.
+        fence(Ordering::SeqCst);
+        Key(key)
+    }
+
+    /// Deregister waiter without notifying it.
+    ///
+    /// Returns `false` if the waiter is already notified and removed from this queue.
+    pub fn cancel(&self, key: Key) -> bool {
+        self.remove(|entry| entry.key == key.0).is_some()
+    }
+
+    /// Notify single waiter of given kind. Returns `false` if no waiter was found.
+    ///
+    /// This is called on every commit, so it doesn't issue a fence and returns
+    /// right away if no waiter is registered. The state change waiters wait for
+    /// should be made by a sequentially consistent operation before this call,
+    /// like commits of sequences, or the notification may be missed.
+    pub fn notify(&self, kind: Kind) -> bool {
+        if self.waiting[slot(kind)].load(Ordering::SeqCst) == 0 {
+            return false;
+        }
+
+        match self.remove(|entry| entry.kind == kind) {
+            Some(value) => {
+                value.notify();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Notify every waiters of given kind. Returns `false` if no waiter was found.
+    pub fn notify_every(&self, kind: Kind) -> bool {
+        fence(Ordering::SeqCst);
+
+        if self.waiting[slot(kind)].load(Ordering::SeqCst) == 0 {
+            return false;
+        }
+
+        let entries = {
+            let mut list = self.list.lock();
+            let (notified, rest) = mem::take(&mut list.entries)
+                .into_iter()
+                .partition::<Vec<_>, _>(|entry| entry.kind == kind);
+            list.entries = rest;
+            self.waiting[slot(kind)].store(0, Ordering::SeqCst);
+            notified
+        };
+
+        let found = !entries.is_empty();
+        for entry in entries {
+            entry.value.notify();
+        }
+        found
+    }
+
+    /// Notify every waiters regardless of its kind.
+    pub fn notify_all(&self) {
+        fence(Ordering::SeqCst);
+
+        let entries = {
+            let mut list = self.list.lock();
+            for waiting in &self.waiting {
+                waiting.store(0, Ordering::SeqCst);
+            }
+            mem::take(&mut list.entries)
+        };
+
+        for entry in entries {
+            entry.value.notify();
+        }
+    }
+
+    fn remove<F: Fn(&Entry<T>) -> bool>(&self, pred: F) -> Option<T> {
+        let mut list = self.list.lock();
+        let idx = list.entries.iter().position(pred)?;
+        let entry = list.entries.swap_remove(idx);
+        self.waiting[slot(entry.kind)].fetch_sub(1, Ordering::SeqCst);
+
+        Some(entry.value)
+    }
+}
+
+impl<T: Notify> Default for WaitList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Notify> fmt::Debug for WaitList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WaitList")
+            .field("senders", &self.len(Kind::Send))
+            .field("receivers", &self.len(Kind::Receive))
+            .finish()
+    }
+}
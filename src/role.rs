@@ -1,13 +1,29 @@
 
-use std::marker::PhantomData;
-use std::ptr;
+use core::marker::PhantomData;
+use core::ptr;
 
 pub trait Role: private::Sealed {
     type Item;
     type Input;
     type Output;
 
+    /// Kind of the channel side which performs this role.
+    const KIND: Kind;
+
+    /// Move value into or out of `target` slot.
+    ///
+    /// # Safety
+    ///
+    /// `target` must point to a slot which is claimed by the caller.
+    /// For `Send` the slot must be uninitialized, and for `Receive` it must be initialized.
     unsafe fn interact(target: *mut Self::Item, input: Self::Input) -> Self::Output;
+
+    /// Clean up `target` slot after `interact` if it cannot be committed.
+    ///
+    /// # Safety
+    ///
+    /// `target` must be the slot passed to `interact` which is not committed.
+    unsafe fn revoke(target: *mut Self::Item);
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +32,16 @@ pub enum Kind {
     Receive,
 }
 
+impl Kind {
+    /// Returns the kind of the opposite side of the channel.
+    pub fn counterpart(self) -> Kind {
+        match self {
+            Kind::Send => Kind::Receive,
+            Kind::Receive => Kind::Send,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Send<T> {
     _marker: PhantomData<T>,
@@ -26,14 +52,28 @@ pub struct Receive<T> {
     _marker: PhantomData<T>,
 }
 
+/// Receive role which leaves the value in the slot and lends it to the caller.
+///
+/// Borrower is responsible to drop the value before the slot is committed.
+#[derive(Debug)]
+pub struct Borrow<T> {
+    _marker: PhantomData<T>,
+}
+
 impl<T> Role for Send<T> {
     type Item = T;
     type Input = T;
     type Output = ();
 
+    const KIND: Kind = Kind::Send;
+
     unsafe fn interact(target: *mut T, input: T) {
         ptr::write(target, input);
     }
+
+    unsafe fn revoke(target: *mut T) {
+        ptr::drop_in_place(target);
+    }
 }
 
 impl<T> Role for Receive<T> {
@@ -41,13 +81,36 @@ impl<T> Role for Receive<T> {
     type Input = ();
     type Output = T;
 
+    const KIND: Kind = Kind::Receive;
+
     unsafe fn interact(target: *mut T, _: ()) -> T {
         ptr::read(target)
     }
+
+    unsafe fn revoke(_: *mut T) {
+        // Value is already moved out, and receiver sequence is never closed.
+    }
+}
+
+impl<T> Role for Borrow<T> {
+    type Item = T;
+    type Input = ();
+    type Output = *mut T;
+
+    const KIND: Kind = Kind::Receive;
+
+    unsafe fn interact(target: *mut T, _: ()) -> *mut T {
+        target
+    }
+
+    unsafe fn revoke(_: *mut T) {
+        // Value is already dropped by the borrower, and receiver sequence is never closed.
+    }
 }
 
 impl<T> private::Sealed for Send<T> {}
 impl<T> private::Sealed for Receive<T> {}
+impl<T> private::Sealed for Borrow<T> {}
 
 mod private {
     pub trait Sealed {}
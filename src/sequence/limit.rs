@@ -0,0 +1,139 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::{RwLock, PoisonError};
+
+use counter::{Counter, AtomicCounter};
+use sequence::Limit;
+
+/// Minimum of given limits, like the slowest one of dependent sequences.
+///
+/// # Panics
+///
+/// Panics on `count()` if no limit is given.
+#[derive(Debug, Clone)]
+pub struct MinOf<C>(pub C);
+
+/// Given limit shifted by a fixed amount, like the sender of a ring
+/// which can go ahead of the receiver by the capacity.
+#[derive(Debug, Clone)]
+pub struct Offset<L>(pub L, pub usize);
+
+/// Minimum of the base limit and gating counters, which can be added or removed at runtime.
+///
+/// Limits should never go backward, so gating counter should not be behind
+/// counts which are already claimed under this limit when it's added.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Registry<L> {
+    base: L,
+    gates: RwLock<Vec<Arc<AtomicCounter>>>,
+}
+
+/// Counter itself limits sequences following it.
+impl Limit for AtomicCounter {
+    fn count(&self) -> Counter {
+        match self.fetch() {
+            Ok(count) => count,
+            Err(count) => count,
+        }
+    }
+}
+
+impl<L: Limit + ?Sized> Limit for &L {
+    fn count(&self) -> Counter {
+        (**self).count()
+    }
+}
+
+impl<L: Limit + ?Sized> Limit for Arc<L> {
+    fn count(&self) -> Counter {
+        (**self).count()
+    }
+}
+
+fn min<I: IntoIterator<Item=Counter>>(counts: I) -> Option<Counter> {
+    counts.into_iter().fold(None, |min, count| match min {
+        Some(min) if min <= count => Some(min),
+        _ => Some(count),
+    })
+}
+
+fn min_of<L: Limit>(limits: &[L]) -> Counter {
+    min(limits.iter().map(Limit::count)).expect("MinOf requires at least one limit")
+}
+
+impl<L: Limit, const N: usize> Limit for MinOf<[L; N]> {
+    fn count(&self) -> Counter {
+        min_of(&self.0)
+    }
+}
+
+impl<L: Limit> Limit for MinOf<&[L]> {
+    fn count(&self) -> Counter {
+        min_of(self.0)
+    }
+}
+
+impl<L: Limit> Limit for MinOf<Vec<L>> {
+    fn count(&self) -> Counter {
+        min_of(&self.0)
+    }
+}
+
+impl<L: Limit> Limit for Offset<L> {
+    fn count(&self) -> Counter {
+        self.0.count() + self.1
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: Limit> Registry<L> {
+    pub fn new(base: L) -> Self {
+        Registry {
+            base,
+            gates: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn base(&self) -> &L {
+        &self.base
+    }
+
+    /// Add a gating counter.
+    pub fn add(&self, gate: Arc<AtomicCounter>) {
+        self.gates.write().unwrap_or_else(PoisonError::into_inner).push(gate);
+    }
+
+    /// Remove a gating counter. Returns `false` if it's not registered.
+    pub fn remove(&self, gate: &Arc<AtomicCounter>) -> bool {
+        let mut gates = self.gates.write().unwrap_or_else(PoisonError::into_inner);
+
+        match gates.iter().position(|g| Arc::ptr_eq(g, gate)) {
+            Some(idx) => {
+                gates.swap_remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of registered gating counters.
+    pub fn len(&self) -> usize {
+        self.gates.read().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<L: Limit> Limit for Registry<L> {
+    fn count(&self) -> Counter {
+        let gates = self.gates.read().unwrap_or_else(PoisonError::into_inner);
+        let base = self.base.count();
+
+        min(gates.iter().map(Limit::count)).map_or(base, |gate| if gate < base { gate } else { base })
+    }
+}
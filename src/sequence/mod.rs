@@ -1,20 +1,51 @@
 
-use std::fmt;
+use core::fmt;
+use core::hint;
 
-use counter::{Counter, AtomicCounter};
+use counter::{Counter, CounterRange, AtomicCounter};
 
 pub mod owned;
 pub mod shared;
+mod limit;
+
+pub use self::limit::{MinOf, Offset};
+#[cfg(feature = "std")]
+pub use self::limit::Registry;
 
 pub trait Sequence: Default {
     type Cache: fmt::Debug;
 
+    /// Whether other caches can claim after given one, so `revert` may fail.
+    ///
+    /// Counterparts of sequences without it never see abandoned slots,
+    /// so they can skip checking them.
+    const MAY_ABANDON: bool = false;
+
     fn cache<L: Limit>(&self, limit: &L) -> Result<Self::Cache, CacheError>;
     fn counter(&self) -> &AtomicCounter;
 
     fn claim<L: Limit>(&self, cache: &mut Self::Cache, limit: &L) -> Option<Counter>;
+
+    /// Commit claimed counter.
+    ///
+    /// Commits should be sequentially consistent, as waiters are notified after them
+    /// without a fence. See `WaitList::notify`.
     fn commit(&self, cache: &mut Self::Cache, count: Counter) -> Result<(), CommitError>;
 
+    /// Claim up to `max` contiguous counters at once.
+    fn claim_n<L: Limit>(
+        &self, cache: &mut Self::Cache, limit: &L, max: usize
+    ) -> Option<CounterRange>;
+
+    /// Commit contiguous counters claimed by `claim_n` at once.
+    fn commit_n(&self, cache: &mut Self::Cache, range: CounterRange) -> Result<(), CommitError>;
+
+    /// Give back claimed but not committed counters, which should be the last ones claimed.
+    ///
+    /// This fails if other claims are made after given range,
+    /// as this sequence can't be committed beyond the hole anymore.
+    fn revert(&self, cache: &mut Self::Cache, range: CounterRange) -> Result<(), RevertError>;
+
     fn fetch_last(&self) -> Counter {
         match self.counter().fetch() {
             Ok(count) => count,
@@ -32,9 +63,59 @@ pub enum CacheError {
 #[derive(Debug)]
 pub struct CommitError;
 
+#[derive(Debug)]
+pub struct RevertError;
+
 /// Sequences that can have more than one caches at the same time.
 pub trait MultiCache: Sequence {}
 
+/// Receiver sequences which senders can claim from as well, to evict messages.
+pub trait Evict: Sequence {}
+
 pub trait Limit {
     fn count(&self) -> Counter;
 }
+
+/// Exponential backoff for spin loops waiting for other threads.
+///
+/// After spinning for a while, it yields to the scheduler so preempted thread
+/// which holds prior counter can make progress.
+#[derive(Debug)]
+pub(crate) struct Backoff {
+    step: u32,
+}
+
+const SPIN_LIMIT: u32 = 6;
+
+impl Backoff {
+    pub fn new() -> Self {
+        Backoff {
+            step: 0,
+        }
+    }
+
+    pub fn snooze(&mut self) {
+        if self.step < SPIN_LIMIT {
+            for _ in 0..(1 << self.step) {
+                hint::spin_loop();
+            }
+            self.step += 1;
+        } else {
+            yield_now();
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn yield_now() {
+    ::std::thread::yield_now();
+}
+
+/// Without `std`, there's no scheduler to yield to.
+#[cfg(not(feature = "std"))]
+fn yield_now() {
+    hint::spin_loop();
+}
+
+#[cfg(test)]
+mod tests;
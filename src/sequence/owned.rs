@@ -1,21 +1,60 @@
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use core::sync::atomic::{AtomicBool, Ordering};
 
-use counter::{Counter, AtomicCounter};
-use sequence::{Sequence, Limit, CacheError, CommitError};
+use core::cmp;
+
+use counter::{Counter, CounterRange, AtomicCounter};
+use sequence::{Sequence, Limit, CacheError, CommitError, RevertError};
 
 #[derive(Debug, Default)]
+#[repr(C)]
 pub struct Owned {
     count: AtomicCounter,
     has_cache: AtomicBool,
 }
 
+/// Claimed counters are not taken by the cache until they're committed,
+/// so claims leaked without commit or revert are claimed again instead of being
+/// published or skipped by later commits.
 #[derive(Debug)]
 pub struct Cache {
+    /// Next counter to commit, which is the same as the counter of the sequence.
     count: Counter,
     limit: Counter,
 }
 
+impl Owned {
+    pub const fn new() -> Self {
+        Owned {
+            count: AtomicCounter::new(Counter::new(0)),
+            has_cache: AtomicBool::new(false),
+        }
+    }
+
+    /// Allow to create new cache, as exclusive access guarantees previous one is gone.
+    pub fn reset_cache(&mut self) {
+        *self.has_cache.get_mut() = false;
+    }
+
+    /// Give up the cache, so new one can be created.
+    ///
+    /// Cache previously created should not be used after this call.
+    pub fn release_cache(&self) {
+        self.has_cache.store(false, Ordering::Release);
+    }
+
+    /// Returns the counter which will be claimed next, without claiming it.
+    pub fn peek<L: Limit>(&self, cache: &Cache, limit: &L) -> Option<Counter> {
+        debug_assert!(cache.count <= cache.limit);
+
+        if cache.count == cache.limit && limit.count() == cache.count {
+            None
+        } else {
+            Some(cache.count)
+        }
+    }
+}
+
 impl Sequence for Owned {
     type Cache = Cache;
 
@@ -50,20 +89,58 @@ impl Sequence for Owned {
         if cache.count == cache.limit {
             None
         } else {
-            let count = cache.count;
-            cache.count = count + 1;
-            Some(count)
+            Some(cache.count)
         }
     }
 
     fn commit(&self, cache: &mut Cache, count: Counter) -> Result<(), CommitError> {
+        debug_assert_eq!(cache.count, count);
+
         match self.count.incr() {
             None => Err(CommitError),
             Some(prev) => {
                 debug_assert_eq!(prev, count);
-                debug_assert_eq!(cache.count, prev + 1);
+                cache.count = count + 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn claim_n<L: Limit>(&self, cache: &mut Cache, limit: &L, max: usize) -> Option<CounterRange> {
+        debug_assert!(cache.count <= cache.limit);
+
+        if ((cache.limit - cache.count) as usize) < max {
+            let recent_limit = limit.count();
+            debug_assert!(recent_limit >= cache.limit);
+            cache.limit = recent_limit;
+        }
+
+        let available = (cache.limit - cache.count) as usize;
+
+        if available == 0 {
+            None
+        } else {
+            Some(Counter::range(cache.count, cache.count + cmp::min(available, max)))
+        }
+    }
+
+    fn commit_n(&self, cache: &mut Cache, range: CounterRange) -> Result<(), CommitError> {
+        debug_assert_eq!(cache.count, range.start);
+
+        match self.count.add(range.len()) {
+            None => Err(CommitError),
+            Some(prev) => {
+                debug_assert_eq!(prev, range.start);
+                cache.count = range.end;
                 Ok(())
             }
         }
     }
+
+    fn revert(&self, cache: &mut Cache, range: CounterRange) -> Result<(), RevertError> {
+        // Nobody else can claim from owned sequence, and uncommitted claims
+        // are not taken by the cache. So nothing to give back.
+        debug_assert!(cache.count <= range.start);
+        Ok(())
+    }
 }
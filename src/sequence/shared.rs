@@ -1,8 +1,9 @@
 
-use std::sync::atomic::Ordering;
+use core::sync::atomic::Ordering;
+use core::cmp;
 
-use counter::{Counter, AtomicCounter};
-use sequence::{Sequence, Limit, MultiCache, CacheError, CommitError};
+use counter::{Counter, CounterRange, AtomicCounter};
+use sequence::{Sequence, Limit, MultiCache, Evict, CacheError, CommitError, RevertError, Backoff};
 
 #[derive(Debug, Default)]
 pub struct Shared {
@@ -15,10 +16,20 @@ pub struct Cache {
     limit: Counter,
 }
 
+/// Shared sequence for a single half which can't be cloned.
+///
+/// Counters are claimed atomically like `Shared`, so senders can evict messages
+/// racing with the only receiver. See `bounded::overwriting_queue`.
+#[derive(Debug, Default)]
+pub struct Evictable(Shared);
+
 impl MultiCache for Shared {}
+impl Evict for Shared {}
+impl Evict for Evictable {}
 
 impl Sequence for Shared {
     type Cache = Cache;
+    const MAY_ABANDON: bool = true;
 
     fn cache<L: Limit>(&self, limit: &L) -> Result<Cache, CacheError> {
         match self.count.fetch() {
@@ -36,6 +47,7 @@ impl Sequence for Shared {
     fn claim<L: Limit>(&self, cache: &mut Cache, limit: &L) -> Option<Counter> {
         // Increase claimed counter
         let claimed = self.claimed.incr()?;
+        let mut backoff = Backoff::new();
 
         loop {
             // Fetch recent limit if cached limit is lower than claimed
@@ -51,7 +63,8 @@ impl Sequence for Shared {
                     Ok(()) => return None,
                     Err(prev) => {
                         // Recheck limit if revert is failed
-                        debug_assert!(prev.map_or(true, |prev| prev > claimed + 1));
+                        debug_assert!(prev.is_none_or(|prev| prev > claimed + 1));
+                        backoff.snooze();
                         continue;
                     }
                 }
@@ -62,12 +75,97 @@ impl Sequence for Shared {
     }
 
     fn commit(&self, _cache: &mut Cache, count: Counter) -> Result<(), CommitError> {
+        let mut backoff = Backoff::new();
+
         loop {
-            match self.count.comp_swap(count, count + 1, Ordering::AcqRel) {
+            match self.count.comp_swap(count, count + 1, Ordering::SeqCst) {
                 Ok(()) => return Ok(()),
-                Err(Some(_)) => continue, // Other thread modified it. Retry
+                Err(Some(_)) => backoff.snooze(), // Other thread modified it. Retry
                 Err(None) => return Err(CommitError), // Sequence closed.
             }
         }
     }
+
+    fn claim_n<L: Limit>(&self, cache: &mut Cache, limit: &L, max: usize) -> Option<CounterRange> {
+        let mut start = self.claimed.fetch().ok()?;
+        let mut backoff = Backoff::new();
+
+        loop {
+            // Fetch recent limit if cached limit is not enough
+            if cache.limit - start < max as isize {
+                let recent_limit = limit.count();
+                debug_assert!(recent_limit >= cache.limit);
+                cache.limit = recent_limit;
+            }
+
+            let available = cache.limit - start;
+
+            // Other thread may claimed beyond the limit, which will be reverted soon
+            if available <= 0 {
+                return None;
+            }
+
+            let end = start + cmp::min(available as usize, max);
+
+            match self.claimed.comp_swap(start, end, Ordering::AcqRel) {
+                Ok(()) => return Some(Counter::range(start, end)),
+                Err(Some(prev)) => {
+                    // Other thread claimed first. Retry from there
+                    start = prev;
+                    backoff.snooze();
+                }
+                Err(None) => return None,
+            }
+        }
+    }
+
+    fn commit_n(&self, _cache: &mut Cache, range: CounterRange) -> Result<(), CommitError> {
+        let mut backoff = Backoff::new();
+
+        loop {
+            match self.count.comp_swap(range.start, range.end, Ordering::SeqCst) {
+                Ok(()) => return Ok(()),
+                Err(Some(_)) => backoff.snooze(), // Other thread modified it. Retry
+                Err(None) => return Err(CommitError), // Sequence closed.
+            }
+        }
+    }
+
+    fn revert(&self, _cache: &mut Cache, range: CounterRange) -> Result<(), RevertError> {
+        self.claimed.comp_swap(range.end, range.start, Ordering::AcqRel)
+            .map_err(|_| RevertError)
+    }
+}
+
+impl Sequence for Evictable {
+    type Cache = Cache;
+    const MAY_ABANDON: bool = true;
+
+    fn cache<L: Limit>(&self, limit: &L) -> Result<Cache, CacheError> {
+        self.0.cache(limit)
+    }
+
+    fn counter(&self) -> &AtomicCounter {
+        self.0.counter()
+    }
+
+    fn claim<L: Limit>(&self, cache: &mut Cache, limit: &L) -> Option<Counter> {
+        self.0.claim(cache, limit)
+    }
+
+    fn commit(&self, cache: &mut Cache, count: Counter) -> Result<(), CommitError> {
+        self.0.commit(cache, count)
+    }
+
+    fn claim_n<L: Limit>(&self, cache: &mut Cache, limit: &L, max: usize) -> Option<CounterRange> {
+        self.0.claim_n(cache, limit, max)
+    }
+
+    fn commit_n(&self, cache: &mut Cache, range: CounterRange) -> Result<(), CommitError> {
+        self.0.commit_n(cache, range)
+    }
+
+    fn revert(&self, cache: &mut Cache, range: CounterRange) -> Result<(), RevertError> {
+        self.0.revert(cache, range)
+    }
 }
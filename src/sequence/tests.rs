@@ -0,0 +1,76 @@
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use counter::{Counter, AtomicCounter};
+use sequence::{Sequence, Limit, MinOf, Offset};
+#[cfg(feature = "std")]
+use sequence::Registry;
+use sequence::owned::Owned;
+
+#[test]
+fn test_min_of() {
+    let a = AtomicCounter::new(Counter::new(3));
+    let b = AtomicCounter::new(Counter::new(5));
+
+    assert_eq!(MinOf([&a, &b]).count(), Counter::new(3));
+    a.add(4);
+    assert_eq!(MinOf([&a, &b]).count(), Counter::new(5));
+    assert_eq!(MinOf(vec![&b]).count(), Counter::new(5));
+
+    // Closed counter still limits with its last value
+    b.close();
+    assert_eq!(MinOf([&a, &b]).count(), Counter::new(5));
+}
+
+#[test]
+fn test_offset() {
+    let a = AtomicCounter::new(Counter::new(3));
+    let b = AtomicCounter::new(Counter::new(5));
+
+    assert_eq!(Offset(&a, 4).count(), Counter::new(7));
+    assert_eq!(Offset(MinOf([&a, &b]), 1).count(), Counter::new(4));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_registry() {
+    let base = AtomicCounter::new(Counter::new(10));
+    let registry = Registry::new(&base);
+    assert_eq!(registry.count(), Counter::new(10));
+
+    let gate = Arc::new(AtomicCounter::new(Counter::new(4)));
+    registry.add(gate.clone());
+    assert_eq!(registry.len(), 1);
+    assert_eq!(registry.count(), Counter::new(4));
+
+    gate.add(8);
+    assert_eq!(registry.count(), Counter::new(10));
+    gate.add(1_000_000 - 12);
+    assert_eq!(registry.count(), Counter::new(10));
+
+    assert!(registry.remove(&gate));
+    assert!(!registry.remove(&gate));
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn test_sequence_with_combinators() {
+    let producer = Owned::default();
+    let journal = AtomicCounter::default();
+    let replicate = AtomicCounter::default();
+
+    // Producer can go ahead of the slowest consumer by 4
+    let gates = [&journal, &replicate];
+    let limit = Offset(MinOf(gates), 4);
+    let mut cache = producer.cache(&limit).unwrap();
+
+    let range = producer.claim_n(&mut cache, &limit, 10).unwrap();
+    assert_eq!(range.len(), 4);
+    producer.commit_n(&mut cache, range).unwrap();
+    assert!(producer.claim(&mut cache, &limit).is_none());
+
+    journal.add(2);
+    assert!(producer.claim(&mut cache, &limit).is_none());
+    replicate.add(1);
+    assert!(producer.claim(&mut cache, &limit).is_some());
+}
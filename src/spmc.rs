@@ -0,0 +1,27 @@
+//! Single producer multi consumer channel.
+//!
+//! Receivers can be cloned and share `Shared` sequence, while the sender
+//! uses `Owned` sequence which claims slots without atomic read-modify-write.
+//! Receivers never check for abandoned slots, as the owned sender always gives
+//! its unused slots back. Each message is received by only one of the receivers.
+//!
+//! ```
+//! let (mut tx, mut rx) = ringbuf::spmc::channel::<u32>(4);
+//! let mut rx2 = rx.clone();
+//!
+//! tx.try_send(1).unwrap();
+//! tx.try_send(2).unwrap();
+//! assert_eq!(rx2.try_recv(), Ok(Some(1)));
+//! assert_eq!(rx.try_recv(), Ok(Some(2)));
+//! ```
+
+use sequence::owned::Owned;
+use sequence::shared::Shared;
+use queue::bounded;
+
+pub type Sender<T> = bounded::Sender<Owned, Shared, T>;
+pub type Receiver<T> = bounded::Receiver<Owned, Shared, T>;
+
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    bounded::queue(capacity)
+}
@@ -0,0 +1,230 @@
+//! Single producer single consumer channel specialized for throughput.
+//!
+//! Each counter is modified only by its own half, so messages are committed with
+//! plain `Release` stores instead of atomic read-modify-write. Each half caches
+//! the counter of the other one, and only reloads it when it runs out of slots.
+//!
+//! In return, closing is tracked by separate flags and halves never block.
+//!
+//! ```
+//! let (mut tx, mut rx) = ringbuf::spsc::fast::channel::<u32>(4);
+//!
+//! tx.try_send(1).unwrap();
+//! assert_eq!(rx.try_recv(), Ok(Some(1)));
+//! drop(tx);
+//! assert_eq!(rx.try_recv(), Ok(None));
+//! ```
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::ops::Drop;
+use core::ptr;
+
+use counter::{Counter, CounterRange, AtomicCounter};
+use buffer::{Buffer, BufRange};
+use error::{SendError, RecvError};
+
+#[derive(Debug)]
+struct Head {
+    /// Counter of the next slot to send. Only the sender modifies it, and it's never closed.
+    sender: AtomicCounter,
+    /// Counter of the next slot to receive. Only the receiver modifies it, and it's never closed.
+    receiver: AtomicCounter,
+    sender_closed: AtomicBool,
+    receiver_closed: AtomicBool,
+}
+
+#[derive(Debug)]
+pub struct Sender<T> {
+    buf: Buffer<Head, T>,
+    count: Counter,
+    /// Cached receiver counter plus capacity.
+    limit: Counter,
+    closed: bool,
+}
+
+#[derive(Debug)]
+pub struct Receiver<T> {
+    buf: Buffer<Head, T>,
+    count: Counter,
+    /// Cached sender counter.
+    limit: Counter,
+    closed: bool,
+}
+
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let buf = Buffer::new(Head {
+        sender: AtomicCounter::default(),
+        receiver: AtomicCounter::default(),
+        sender_closed: false.into(),
+        receiver_closed: false.into(),
+    }, capacity);
+
+    let sender = Sender {
+        buf: buf.clone(),
+        count: Counter::default(),
+        limit: Counter::default() + capacity,
+        closed: false,
+    };
+    let receiver = Receiver {
+        buf,
+        count: Counter::default(),
+        limit: Counter::default(),
+        closed: false,
+    };
+
+    (sender, receiver)
+}
+
+fn load(counter: &AtomicCounter) -> Counter {
+    match counter.fetch() {
+        Ok(count) | Err(count) => count,
+    }
+}
+
+impl Head {
+    fn len(&self) -> usize {
+        let receiver = load(&self.receiver);
+        (load(&self.sender) - receiver) as usize
+    }
+}
+
+impl BufRange for Head {
+    fn range(&self) -> CounterRange {
+        Counter::range(load(&self.receiver), load(&self.sender))
+    }
+}
+
+impl<T> Sender<T> {
+    pub fn is_closed(&self) -> bool {
+        self.closed || self.is_disconnected()
+    }
+
+    /// Whether the receiver is dropped or closed the channel.
+    pub fn is_disconnected(&self) -> bool {
+        self.buf.head().receiver_closed.load(Ordering::Acquire)
+    }
+
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.buf.head().sender_closed.store(true, Ordering::Release);
+    }
+
+    /// Number of messages in the buffer. It's only a snapshot like `bounded::Sender::len`.
+    pub fn len(&self) -> usize {
+        self.buf.head().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    pub fn try_send(&mut self, msg: T) -> Result<(), SendError<T>> {
+        if self.closed {
+            return Err(SendError::Closed(msg));
+        }
+
+        let head = self.buf.head();
+
+        if head.receiver_closed.load(Ordering::Acquire) {
+            return Err(SendError::Disconnected(msg));
+        }
+
+        if self.count == self.limit {
+            self.limit = load(&head.receiver) + self.buf.capacity();
+
+            if self.count == self.limit {
+                return Err(SendError::BufferFull(msg));
+            }
+        }
+
+        unsafe {
+            ptr::write(self.buf.get(self.count), msg);
+        }
+
+        self.count += 1;
+        head.sender.store(self.count);
+        Ok(())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.buf.head().sender_closed.store(true, Ordering::Release);
+    }
+}
+
+impl<T> Receiver<T> {
+    pub fn is_closed(&self) -> bool {
+        self.closed || self.is_disconnected()
+    }
+
+    /// Whether the sender is dropped or closed the channel.
+    ///
+    /// Messages sent before that can still be received.
+    pub fn is_disconnected(&self) -> bool {
+        self.buf.head().sender_closed.load(Ordering::Acquire)
+    }
+
+    /// Close the channel. Remaining messages are dropped with the channel.
+    pub fn close(&mut self) {
+        self.closed = true;
+        self.buf.head().receiver_closed.store(true, Ordering::Release);
+    }
+
+    /// Number of messages in the buffer. It's only a snapshot like `bounded::Receiver::len`.
+    pub fn len(&self) -> usize {
+        self.buf.head().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Receive a message if available.
+    ///
+    /// Returns `Ok(None)` if the channel is closed and every messages are received.
+    pub fn try_recv(&mut self) -> Result<Option<T>, RecvError> {
+        if self.closed {
+            return Ok(None);
+        }
+
+        let head = self.buf.head();
+
+        if self.count == self.limit {
+            self.limit = load(&head.sender);
+
+            if self.count == self.limit {
+                if !head.sender_closed.load(Ordering::Acquire) {
+                    return Err(RecvError);
+                }
+
+                // Sender may sent right before it's closed. Check it again
+                self.limit = load(&head.sender);
+
+                if self.count == self.limit {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let msg = unsafe { ptr::read(self.buf.get(self.count)) };
+
+        self.count += 1;
+        head.receiver.store(self.count);
+        Ok(Some(msg))
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.buf.head().receiver_closed.store(true, Ordering::Release);
+    }
+}
@@ -0,0 +1,45 @@
+//! Single producer single consumer channel.
+//!
+//! `channel` and its halves require `std` feature.
+//!
+//! Both halves use `Owned` sequence, which claims slots without atomic read-modify-write
+//! as nobody else can claim from it. Halves can't be cloned. The receiver never checks
+//! for abandoned slots, as the owned sender always gives its unused slots back.
+//! Commits still need atomic read-modify-write, as the channel can be closed
+//! and waiters are notified concurrently.
+//! See `fast` module for leaner version which also commits without it,
+//! `StaticRing` for the one without heap allocation, and `region` module
+//! for the one over shared memory of processes.
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! let (mut tx, mut rx) = ringbuf::spsc::channel::<u32>(4);
+//!
+//! tx.try_send(1).unwrap();
+//! assert_eq!(rx.try_recv(), Ok(Some(1)));
+//! # }
+//! ```
+
+#[cfg(feature = "std")]
+use sequence::owned::Owned;
+#[cfg(feature = "std")]
+use queue::bounded;
+
+pub mod fast;
+pub mod region;
+mod static_ring;
+
+pub use self::static_ring::{StaticRing, Producer, Consumer};
+
+#[cfg(feature = "std")]
+pub type Sender<T> = bounded::Sender<Owned, Owned, T>;
+#[cfg(feature = "std")]
+pub type Receiver<T> = bounded::Receiver<Owned, Owned, T>;
+
+#[cfg(feature = "std")]
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    bounded::queue(capacity)
+}
+
+#[cfg(test)]
+mod tests;
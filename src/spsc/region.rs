@@ -0,0 +1,333 @@
+//! Single producer single consumer ring over a memory region given by the caller.
+//!
+//! The region holds both the header and the slots, so processes which map
+//! the same shared memory can send messages to each other. Messages should be
+//! plain data, as pointers in them are meaningless to other processes.
+//!
+//! ```
+//! use ringbuf::spsc::region::Region;
+//!
+//! // Memory of u64 is aligned enough for the header
+//! let mut memory = vec![0u64; Region::<u32>::size_of(16) / 8];
+//! let len = memory.len() * 8;
+//! let ptr = memory.as_mut_ptr() as *mut u8;
+//!
+//! let region = unsafe { Region::<u32>::init(ptr, len) }.unwrap();
+//! // Other process may attach to the same region
+//! let other = unsafe { Region::<u32>::attach(ptr, len) }.unwrap();
+//!
+//! let mut tx = region.producer().unwrap();
+//! let mut rx = other.consumer().unwrap();
+//! tx.try_send(42).unwrap();
+//! assert_eq!(rx.try_recv(), Ok(42));
+//! ```
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::mem;
+use core::ptr;
+
+use counter::{Counter, AtomicCounter};
+use sequence::{Sequence, Offset};
+use sequence::owned::{self, Owned};
+use buffer::is_valid_capacity;
+use error::{SendError, RecvError};
+
+/// Written after the header is initialized.
+const MAGIC: usize = 0x5249_4e47;
+
+/// Layout of the start of the region, which is followed by slots.
+#[repr(C)]
+struct Header {
+    sender: Owned,
+    receiver: Owned,
+    magic: AtomicUsize,
+    slot_size: usize,
+    capacity: usize,
+}
+
+/// Ring in the memory region, which doesn't own the memory.
+#[derive(Debug)]
+pub struct Region<T: Copy> {
+    header: *const Header,
+    slots: *mut T,
+    capacity: usize,
+}
+
+#[derive(Debug)]
+pub struct Producer<T: Copy> {
+    region: Region<T>,
+    cache: owned::Cache,
+}
+
+#[derive(Debug)]
+pub struct Consumer<T: Copy> {
+    region: Region<T>,
+    cache: owned::Cache,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RegionError {
+    /// Region is not aligned for the header or messages.
+    Misaligned,
+    /// Region is too small to hold the header and a slot.
+    TooSmall,
+    /// Region is not initialized, initialized for different type of messages,
+    /// or its header is corrupted.
+    Incompatible,
+    /// Other half of the same side is already attached.
+    AlreadyAttached,
+}
+
+unsafe impl<T: Copy + Send> Send for Region<T> {}
+unsafe impl<T: Copy + Send> Sync for Region<T> {}
+
+fn slots_offset<T>() -> usize {
+    mem::size_of::<Header>().next_multiple_of(mem::align_of::<T>())
+}
+
+impl<T: Copy> Region<T> {
+    /// Size of the region in bytes, which holds up to `capacity` messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size overflows `usize`.
+    pub fn size_of(capacity: usize) -> usize {
+        Self::checked_size_of(capacity).expect("Region size overflows")
+    }
+
+    fn checked_size_of(capacity: usize) -> Option<usize> {
+        capacity.checked_mul(mem::size_of::<T>())?.checked_add(slots_offset::<T>())
+    }
+
+    /// Initialize the region as an empty ring, which holds as many messages as it can.
+    ///
+    /// # Safety
+    ///
+    /// The region should be valid for reads and writes of `len` bytes while this
+    /// and halves from it are alive, and nobody else should access it during this call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is zero sized.
+    pub unsafe fn init(ptr: *mut u8, len: usize) -> Result<Self, RegionError> {
+        assert!(mem::size_of::<T>() != 0, "Zero sized messages are not supported");
+        Self::check(ptr, len)?;
+
+        let mut capacity = (len - slots_offset::<T>()) / mem::size_of::<T>();
+        if !is_valid_capacity(capacity) {
+            capacity = capacity.next_power_of_two() / 2;
+        }
+
+        let header = ptr as *mut Header;
+        ptr::write(header, Header {
+            sender: Owned::new(),
+            receiver: Owned::new(),
+            magic: AtomicUsize::new(0),
+            slot_size: mem::size_of::<T>(),
+            capacity,
+        });
+        (*header).magic.store(MAGIC, Ordering::Release);
+
+        Ok(Region {
+            header,
+            slots: ptr.add(slots_offset::<T>()) as *mut T,
+            capacity,
+        })
+    }
+
+    /// Attach to the ring which is initialized by `init`, possibly by other process.
+    ///
+    /// The header is validated against `len`, so corrupted one is rejected
+    /// with `RegionError::Incompatible` instead of accessing outside of the region.
+    ///
+    /// # Safety
+    ///
+    /// The region should be valid for reads and writes of `len` bytes while this
+    /// and halves from it are alive.
+    pub unsafe fn attach(ptr: *mut u8, len: usize) -> Result<Self, RegionError> {
+        Self::check(ptr, len)?;
+
+        let header = &*(ptr as *const Header);
+        if header.magic.load(Ordering::Acquire) != MAGIC ||
+            header.slot_size != mem::size_of::<T>() ||
+            !is_valid_capacity(header.capacity) ||
+            Self::checked_size_of(header.capacity).is_none_or(|size| size > len)
+        {
+            return Err(RegionError::Incompatible);
+        }
+
+        Ok(Region {
+            header,
+            slots: ptr.add(slots_offset::<T>()) as *mut T,
+            capacity: header.capacity,
+        })
+    }
+
+    fn check(ptr: *mut u8, len: usize) -> Result<(), RegionError> {
+        let align = mem::align_of::<Header>().max(mem::align_of::<T>());
+
+        if !(ptr as usize).is_multiple_of(align) {
+            Err(RegionError::Misaligned)
+        } else if len < Self::size_of(1) {
+            Err(RegionError::TooSmall)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of messages in the ring.
+    pub fn len(&self) -> usize {
+        let header = self.header();
+        let receiver = header.receiver.fetch_last();
+        (header.sender.fetch_last() - receiver) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Create the producer of the ring. Only one of them can be attached at the same time
+    /// regardless of processes, and it's detached when dropped.
+    ///
+    /// If the process which attached the producer crashed before dropping it,
+    /// it stays attached and this method keeps failing with `RegionError::AlreadyAttached`.
+    /// Use `reset_producer` to recover from it.
+    pub fn producer(&self) -> Result<Producer<T>, RegionError> {
+        let region = self.clone();
+        let cache = self.header().sender.cache(&region.sender_limit())
+            .map_err(|_| RegionError::AlreadyAttached)?;
+
+        Ok(Producer {
+            region,
+            cache,
+        })
+    }
+
+    /// Create the consumer of the ring. Only one of them can be attached at the same time
+    /// regardless of processes, and it's detached when dropped.
+    ///
+    /// If the process which attached the consumer crashed before dropping it,
+    /// it stays attached and this method keeps failing with `RegionError::AlreadyAttached`.
+    /// Use `reset_consumer` to recover from it.
+    pub fn consumer(&self) -> Result<Consumer<T>, RegionError> {
+        let header = self.header();
+        let cache = header.receiver.cache(header.sender.counter())
+            .map_err(|_| RegionError::AlreadyAttached)?;
+
+        Ok(Consumer {
+            region: self.clone(),
+            cache,
+        })
+    }
+
+    /// Detach the producer which is not dropped properly, like the one of crashed process.
+    ///
+    /// Message it was writing is discarded, as it's committed only after written.
+    ///
+    /// # Safety
+    ///
+    /// The producer attached before should never be used again.
+    pub unsafe fn reset_producer(&self) {
+        self.header().sender.release_cache();
+    }
+
+    /// Detach the consumer which is not dropped properly, like the one of crashed process.
+    ///
+    /// Message it was reading is received again by next consumer,
+    /// as it's committed only after read.
+    ///
+    /// # Safety
+    ///
+    /// The consumer attached before should never be used again.
+    pub unsafe fn reset_consumer(&self) {
+        self.header().receiver.release_cache();
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*self.header }
+    }
+
+    fn sender_limit(&self) -> Offset<&AtomicCounter> {
+        Offset(self.header().receiver.counter(), self.capacity)
+    }
+
+    fn slot(&self, count: Counter) -> *mut T {
+        let index = if self.capacity.is_power_of_two() {
+            count & (self.capacity - 1)
+        } else {
+            count % self.capacity
+        };
+
+        unsafe {
+            self.slots.add(index)
+        }
+    }
+}
+
+impl<T: Copy> Clone for Region<T> {
+    fn clone(&self) -> Self {
+        Region {
+            header: self.header,
+            slots: self.slots,
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl<T: Copy> Producer<T> {
+    /// Send a message, or returns it back with `SendError::BufferFull` if the ring is full.
+    pub fn try_send(&mut self, msg: T) -> Result<(), SendError<T>> {
+        let region = &self.region;
+        let header = region.header();
+        let count = match header.sender.claim(&mut self.cache, &region.sender_limit()) {
+            Some(count) => count,
+            None => return Err(SendError::BufferFull(msg)),
+        };
+
+        unsafe {
+            ptr::write(region.slot(count), msg);
+        }
+
+        // Sequences of the region are never closed
+        let _ = header.sender.commit(&mut self.cache, count);
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.region.capacity
+    }
+}
+
+impl<T: Copy> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.region.header().sender.release_cache();
+    }
+}
+
+impl<T: Copy> Consumer<T> {
+    /// Receive a message if available.
+    pub fn try_recv(&mut self) -> Result<T, RecvError> {
+        let region = &self.region;
+        let header = region.header();
+        let count = header.receiver.claim(&mut self.cache, header.sender.counter()).ok_or(RecvError)?;
+        let msg = unsafe { ptr::read(region.slot(count)) };
+
+        // Sequences of the region are never closed
+        let _ = header.receiver.commit(&mut self.cache, count);
+        Ok(msg)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.region.capacity
+    }
+}
+
+impl<T: Copy> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.region.header().receiver.release_cache();
+    }
+}
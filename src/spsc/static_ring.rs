@@ -0,0 +1,235 @@
+//! Ring buffer whose storage lives inline, without heap allocation.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::mem::MaybeUninit;
+use core::ops::Drop;
+use core::ptr;
+use core::fmt;
+
+use counter::{Counter, AtomicCounter};
+use sequence::{Sequence, Offset};
+use sequence::owned::{self, Owned};
+use buffer::is_valid_capacity;
+use error::{SendError, RecvError};
+
+/// Ring buffer of `N` slots stored inline, which can be placed in a `static` or on the stack.
+///
+/// It's used by splitting into a `Producer` and a `Consumer`, which borrow the ring.
+/// They never close the ring, so it can be split again after they're dropped
+/// and remaining messages are received by the next consumer.
+///
+/// ```
+/// use ringbuf::spsc::StaticRing;
+///
+/// let mut ring = StaticRing::<u32, 4>::new();
+/// let (mut tx, mut rx) = ring.split();
+///
+/// tx.try_send(1).unwrap();
+/// assert_eq!(rx.try_recv(), Ok(1));
+/// ```
+///
+/// Ring in a plain `static` can be split once by `split_static`.
+///
+/// ```
+/// use ringbuf::spsc::StaticRing;
+///
+/// static RING: StaticRing<u32, 4> = StaticRing::new();
+///
+/// let (mut tx, mut rx) = RING.split_static().unwrap();
+/// assert!(RING.split_static().is_none());
+///
+/// tx.try_send(1).unwrap();
+/// assert_eq!(rx.try_recv(), Ok(1));
+/// ```
+pub struct StaticRing<T, const N: usize> {
+    sender: Owned,
+    receiver: Owned,
+    /// Whether it's split by `split_static`.
+    split: AtomicBool,
+    slots: UnsafeCell<MaybeUninit<[T; N]>>,
+}
+
+pub struct Producer<'a, T: 'a, const N: usize> {
+    ring: &'a StaticRing<T, N>,
+    cache: owned::Cache,
+}
+
+pub struct Consumer<'a, T: 'a, const N: usize> {
+    ring: &'a StaticRing<T, N>,
+    cache: owned::Cache,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for StaticRing<T, N> {}
+
+impl<T, const N: usize> StaticRing<T, N> {
+    /// Create an empty ring.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is not a valid capacity of `Buffer`.
+    pub const fn new() -> Self {
+        assert!(is_valid_capacity(N), "Invalid capacity of the ring");
+
+        StaticRing {
+            sender: Owned::new(),
+            receiver: Owned::new(),
+            split: AtomicBool::new(false),
+            slots: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of messages in the ring.
+    pub fn len(&self) -> usize {
+        let receiver = self.receiver.fetch_last();
+        (self.sender.fetch_last() - receiver) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Split the ring into a producer and a consumer.
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        // Halves of previous split are gone, as they borrowed the ring
+        self.sender.reset_cache();
+        self.receiver.reset_cache();
+
+        self.halves()
+    }
+
+    /// Split the ring into a producer and a consumer without exclusive access,
+    /// so it works on a plain `static`.
+    ///
+    /// Halves may live forever, so it can be split only once.
+    /// Returns `None` if it's already split.
+    pub fn split_static(&'static self) -> Option<(Producer<'static, T, N>, Consumer<'static, T, N>)> {
+        if self.split.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        Some(self.halves())
+    }
+
+    /// Create halves of the ring. Caches of sequences should be available.
+    fn halves(&self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        let ring = self;
+        let sender_cache = ring.sender.cache(&ring.sender_limit())
+            .expect("Sequences of the ring are never closed");
+        let receiver_cache = ring.receiver.cache(ring.sender.counter())
+            .expect("Sequences of the ring are never closed");
+
+        let producer = Producer {
+            ring,
+            cache: sender_cache,
+        };
+        let consumer = Consumer {
+            ring,
+            cache: receiver_cache,
+        };
+
+        (producer, consumer)
+    }
+
+    fn sender_limit(&self) -> Offset<&AtomicCounter> {
+        Offset(self.receiver.counter(), N)
+    }
+
+    fn slot(&self, count: Counter) -> *mut T {
+        let index = if N.is_power_of_two() {
+            count & (N - 1)
+        } else {
+            count % N
+        };
+
+        unsafe {
+            (self.slots.get() as *mut T).add(index)
+        }
+    }
+}
+
+impl<T, const N: usize> Default for StaticRing<T, N> {
+    fn default() -> Self {
+        StaticRing::new()
+    }
+}
+
+impl<T, const N: usize> Drop for StaticRing<T, N> {
+    fn drop(&mut self) {
+        for count in Counter::range(self.receiver.fetch_last(), self.sender.fetch_last()) {
+            unsafe {
+                ptr::drop_in_place(self.slot(count));
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for StaticRing<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticRing")
+            .field("sender", &self.sender)
+            .field("receiver", &self.receiver)
+            .field("split", &self.split)
+            .finish()
+    }
+}
+
+impl<'a, T, const N: usize> Producer<'a, T, N> {
+    /// Send a message, or returns it back with `SendError::BufferFull` if the ring is full.
+    pub fn try_send(&mut self, msg: T) -> Result<(), SendError<T>> {
+        let ring = self.ring;
+        let count = match ring.sender.claim(&mut self.cache, &ring.sender_limit()) {
+            Some(count) => count,
+            None => return Err(SendError::BufferFull(msg)),
+        };
+
+        unsafe {
+            ptr::write(ring.slot(count), msg);
+        }
+
+        // Sequences of the ring are never closed
+        let _ = ring.sender.commit(&mut self.cache, count);
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<'a, T, const N: usize> Consumer<'a, T, N> {
+    /// Receive a message if available.
+    pub fn try_recv(&mut self) -> Result<T, RecvError> {
+        let ring = self.ring;
+        let count = ring.receiver.claim(&mut self.cache, ring.sender.counter()).ok_or(RecvError)?;
+        let msg = unsafe { ptr::read(ring.slot(count)) };
+
+        // Sequences of the ring are never closed
+        let _ = ring.receiver.commit(&mut self.cache, count);
+        Ok(msg)
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<'a, T, const N: usize> fmt::Debug for Producer<'a, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Producer")
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+impl<'a, T, const N: usize> fmt::Debug for Consumer<'a, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Consumer")
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
@@ -0,0 +1,350 @@
+use std::mem;
+use std::slice;
+use std::thread;
+use std::sync::{Arc, Barrier};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use error::{SendError, RecvError};
+use test_util::LoudDrop;
+
+use super::fast;
+use super::StaticRing;
+use super::region::{Region, RegionError};
+
+const COUNT: usize = 100_000;
+
+#[test]
+fn test_fast_wrap_around() {
+    let (mut tx, mut rx) = fast::channel::<usize>(3);
+    assert_eq!(rx.try_recv(), Err(RecvError));
+
+    for i in 0..10 {
+        tx.try_send(i * 2).unwrap();
+        tx.try_send(i * 2 + 1).unwrap();
+        assert_eq!(tx.len(), 2);
+        assert_eq!(rx.try_recv(), Ok(Some(i * 2)));
+        assert_eq!(rx.try_recv(), Ok(Some(i * 2 + 1)));
+    }
+
+    for i in 0..3 {
+        tx.try_send(i).unwrap();
+    }
+    assert_eq!(tx.try_send(3), Err(SendError::BufferFull(3)));
+    assert_eq!(rx.try_recv(), Ok(Some(0)));
+    tx.try_send(3).unwrap();
+    assert_eq!(rx.len(), 3);
+}
+
+#[test]
+fn test_fast_close() {
+    let (mut tx, mut rx) = fast::channel::<usize>(4);
+    tx.try_send(1).unwrap();
+    tx.close();
+    assert_eq!(tx.try_send(2), Err(SendError::Closed(2)));
+
+    // Messages sent before close are received
+    assert!(rx.is_disconnected());
+    assert_eq!(rx.try_recv(), Ok(Some(1)));
+    assert_eq!(rx.try_recv(), Ok(None));
+
+    let (mut tx, rx) = fast::channel::<usize>(4);
+    drop(rx);
+    assert!(tx.is_closed());
+    assert_eq!(tx.try_send(1), Err(SendError::Disconnected(1)));
+}
+
+#[test]
+fn test_fast_drop_unreceived() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let (mut tx, mut rx) = fast::channel::<LoudDrop>(4);
+
+    for _ in 0..3 {
+        tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+    }
+    drop(rx.try_recv());
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+    drop(tx);
+    drop(rx);
+    assert_eq!(drops.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_fast_threaded() {
+    let (mut tx, mut rx) = fast::channel::<usize>(16);
+
+    let handle = thread::spawn(move|| {
+        for i in 0..COUNT {
+            while tx.try_send(i).is_err() {
+                thread::yield_now();
+            }
+        }
+    });
+
+    let mut expected = 0;
+    loop {
+        match rx.try_recv() {
+            Ok(Some(num)) => {
+                assert_eq!(num, expected);
+                expected += 1;
+            }
+            Ok(None) => break,
+            Err(RecvError) => thread::yield_now(),
+        }
+    }
+
+    handle.join().unwrap();
+    assert_eq!(expected, COUNT);
+}
+
+#[test]
+fn test_static_ring() {
+    let mut ring = StaticRing::<usize, 3>::new();
+
+    {
+        let (mut tx, mut rx) = ring.split();
+        for i in 0..10 {
+            tx.try_send(i).unwrap();
+            assert_eq!(rx.try_recv(), Ok(i));
+        }
+
+        for i in 0..3 {
+            tx.try_send(i).unwrap();
+        }
+        assert_eq!(tx.try_send(3), Err(SendError::BufferFull(3)));
+        assert_eq!(rx.try_recv(), Ok(0));
+    }
+
+    // Remaining messages are kept for the next split
+    assert_eq!(ring.len(), 2);
+    let (mut tx, mut rx) = ring.split();
+    tx.try_send(3).unwrap();
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert_eq!(rx.try_recv(), Ok(2));
+    assert_eq!(rx.try_recv(), Ok(3));
+    assert_eq!(rx.try_recv(), Err(RecvError));
+}
+
+#[test]
+fn test_static_ring_static() {
+    static RING: StaticRing<usize, 4> = StaticRing::new();
+
+    // Only one of concurrent splits succeeds
+    let barrier = Arc::new(Barrier::new(2));
+    let handles: Vec<_> = (0..2).map(|_| {
+        let barrier = barrier.clone();
+        thread::spawn(move|| {
+            barrier.wait();
+            RING.split_static()
+        })
+    }).collect();
+    let mut halves: Vec<_> = handles.into_iter().filter_map(|h| h.join().unwrap()).collect();
+    assert_eq!(halves.len(), 1);
+    assert!(RING.split_static().is_none());
+
+    let (mut tx, mut rx) = halves.pop().unwrap();
+    let handle = thread::spawn(move|| {
+        for i in 0..10 {
+            while tx.try_send(i).is_err() {
+                thread::yield_now();
+            }
+        }
+    });
+
+    for i in 0..10 {
+        loop {
+            match rx.try_recv() {
+                Ok(num) => break assert_eq!(num, i),
+                Err(RecvError) => thread::yield_now(),
+            }
+        }
+    }
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_static_ring_drop() {
+    let drops = Arc::new(AtomicUsize::new(0));
+    let mut ring = StaticRing::<LoudDrop, 4>::new();
+
+    {
+        let (mut tx, mut rx) = ring.split();
+        for _ in 0..3 {
+            tx.try_send(LoudDrop(drops.clone(), 0)).unwrap();
+        }
+        drop(rx.try_recv());
+    }
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+    drop(ring);
+    assert_eq!(drops.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_static_ring_threaded() {
+    let mut ring = StaticRing::<usize, 16>::new();
+    let (mut tx, mut rx) = ring.split();
+
+    thread::scope(|scope| {
+        scope.spawn(move|| {
+            for i in 0..COUNT {
+                while tx.try_send(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        for i in 0..COUNT {
+            loop {
+                match rx.try_recv() {
+                    Ok(num) => break assert_eq!(num, i),
+                    Err(RecvError) => thread::yield_now(),
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn test_region() {
+    let mut memory = vec![0u64; Region::<u32>::size_of(6) / 8];
+    let len = memory.len() * 8;
+    let ptr = memory.as_mut_ptr() as *mut u8;
+
+    unsafe {
+        assert_eq!(Region::<u32>::attach(ptr, len).unwrap_err(), RegionError::Incompatible);
+        assert_eq!(Region::<u32>::init(ptr.add(1), len - 1).unwrap_err(), RegionError::Misaligned);
+        assert_eq!(Region::<u32>::init(ptr, Region::<u32>::size_of(0)).unwrap_err(),
+            RegionError::TooSmall);
+    }
+
+    let region = unsafe { Region::<u32>::init(ptr, len) }.unwrap();
+    assert_eq!(region.capacity(), 6);
+    assert_eq!(unsafe { Region::<u64>::attach(ptr, len) }.unwrap_err(), RegionError::Incompatible);
+
+    let other = unsafe { Region::<u32>::attach(ptr, len) }.unwrap();
+    let mut tx = region.producer().unwrap();
+    let mut rx = other.consumer().unwrap();
+    assert_eq!(other.producer().unwrap_err(), RegionError::AlreadyAttached);
+
+    for i in 0..20 {
+        tx.try_send(i).unwrap();
+        assert_eq!(rx.try_recv(), Ok(i));
+    }
+    for i in 0..6 {
+        tx.try_send(i).unwrap();
+    }
+    assert_eq!(tx.try_send(6), Err(SendError::BufferFull(6)));
+
+    // Detached producer can be replaced
+    drop(tx);
+    let mut tx = other.producer().unwrap();
+    assert_eq!(rx.try_recv(), Ok(0));
+    tx.try_send(6).unwrap();
+    assert_eq!(region.len(), 6);
+    for i in 1..7 {
+        assert_eq!(rx.try_recv(), Ok(i));
+    }
+    assert_eq!(rx.try_recv(), Err(RecvError));
+
+    // Halves of crashed process can be detached by force
+    mem::forget(tx);
+    mem::forget(rx);
+    assert_eq!(region.producer().unwrap_err(), RegionError::AlreadyAttached);
+    unsafe {
+        region.reset_producer();
+        other.reset_consumer();
+    }
+    let mut tx = region.producer().unwrap();
+    let mut rx = other.consumer().unwrap();
+    tx.try_send(7).unwrap();
+    assert_eq!(rx.try_recv(), Ok(7));
+}
+
+#[test]
+fn test_region_corrupted() {
+    let mut memory = vec![0u64; Region::<u32>::size_of(6) / 8];
+    let len = memory.len() * 8;
+    let ptr = memory.as_mut_ptr() as *mut u8;
+
+    unsafe {
+        Region::<u32>::init(ptr, len).unwrap();
+    }
+
+    // Header ends with magic number, slot size and capacity
+    let words = unsafe { slice::from_raw_parts_mut(ptr as *mut usize, len / mem::size_of::<usize>()) };
+    let magic = words.iter().position(|&word| word == 0x5249_4e47).unwrap();
+    assert_eq!(words[magic + 2], 6);
+
+    for &capacity in &[0, 7, usize::MAX / 2, usize::MAX] {
+        words[magic + 2] = capacity;
+        assert_eq!(unsafe { Region::<u32>::attach(ptr, len) }.unwrap_err(), RegionError::Incompatible);
+    }
+
+    words[magic + 2] = 6;
+    assert!(unsafe { Region::<u32>::attach(ptr, len) }.is_ok());
+}
+
+/// Map the same file twice, like two processes would do.
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+#[test]
+#[cfg_attr(feature = "ci", ignore)]
+fn test_region_mmap() {
+    use std::fs::{self, OpenOptions};
+    use std::os::raw::{c_int, c_void};
+    use std::os::unix::io::AsRawFd;
+    use std::process;
+
+    extern "C" {
+        fn mmap(addr: *mut c_void, len: usize, prot: c_int, flags: c_int, fd: c_int, offset: i64)
+            -> *mut c_void;
+        fn munmap(addr: *mut c_void, len: usize) -> c_int;
+    }
+
+    const PROT_READ_WRITE: c_int = 0x1 | 0x2;
+    const MAP_SHARED: c_int = 0x1;
+
+    let path = std::env::temp_dir().join(format!("ringbuf-region-{}", process::id()));
+    let file = OpenOptions::new().read(true).write(true).create(true).truncate(true)
+        .open(&path).unwrap();
+    let len = Region::<usize>::size_of(16);
+    file.set_len(len as u64).unwrap();
+
+    let map = || unsafe {
+        let ptr = mmap(std::ptr::null_mut(), len, PROT_READ_WRITE, MAP_SHARED, file.as_raw_fd(), 0);
+        assert_ne!(ptr as isize, -1, "mmap failed");
+        ptr as *mut u8
+    };
+    let (ptr1, ptr2) = (map(), map());
+    assert_ne!(ptr1, ptr2);
+
+    let mut tx = unsafe { Region::<usize>::init(ptr1, len) }.unwrap().producer().unwrap();
+    let mut rx = unsafe { Region::<usize>::attach(ptr2, len) }.unwrap().consumer().unwrap();
+
+    let handle = thread::spawn(move|| {
+        for i in 0..COUNT {
+            while tx.try_send(i).is_err() {
+                thread::yield_now();
+            }
+        }
+    });
+
+    for i in 0..COUNT {
+        loop {
+            match rx.try_recv() {
+                Ok(num) => break assert_eq!(num, i),
+                Err(RecvError) => thread::yield_now(),
+            }
+        }
+    }
+    handle.join().unwrap();
+    drop(rx);
+
+    unsafe {
+        munmap(ptr1 as *mut c_void, len);
+        munmap(ptr2 as *mut c_void, len);
+    }
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,14 @@
+//! Helpers shared by tests of every modules.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts how many times it's dropped, with a payload to tell messages apart.
+#[derive(Debug, Clone)]
+pub struct LoudDrop(pub Arc<AtomicUsize>, pub usize);
+
+impl Drop for LoudDrop {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}